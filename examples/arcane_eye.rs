@@ -0,0 +1,60 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpStream,
+};
+
+use prime_derived::{hierarchy_ethereal_flow, DestinyRiftArcaneScript, EtherealFlowArcaneScript};
+use prime_forge::{forged_trait::ForgedTrait, lost_realm::LostRealm};
+
+#[hierarchy_ethereal_flow]
+#[derive(Default)]
+pub struct Health {
+    #[runic]
+    pub health: i32,
+}
+
+impl ForgedTrait for Health {}
+
+#[derive(DestinyRiftArcaneScript, EtherealFlowArcaneScript)]
+pub struct Collision(#[runic] bool);
+
+fn main() {
+    let mut lost_realm = LostRealm::new();
+    lost_realm
+        .forge_new_object(
+            "Player",
+            (Health {
+                health: 100,
+                ..Default::default()
+            },),
+        )
+        .unwrap();
+    let address = lost_realm.open_arcane_eye(0).unwrap();
+    println!("Arcane Eye open at {}", address);
+
+    // local client driving the realm the way an external tool would
+    let client = std::thread::spawn(move || {
+        let mut stream = TcpStream::connect(address).unwrap();
+        let mut responses = BufReader::new(stream.try_clone().unwrap()).lines();
+        let requests = [
+            r#"{"id":1,"cmd":"list_objects"}"#,
+            r#"{"id":2,"cmd":"write_field","object":"Player","trait":"Health","field":"health","value":42}"#,
+            r#"{"id":3,"cmd":"read_trait","object":"Player","trait":"Health"}"#,
+            r#"{"id":4,"cmd":"watch_rifts"}"#,
+            r#"{"id":5,"cmd":"pause"}"#,
+        ];
+        for request in requests {
+            writeln!(stream, "{}", request).unwrap();
+            println!("-> {}\n<- {}", request, responses.next().unwrap().unwrap());
+        }
+        println!("<- {}", responses.next().unwrap().unwrap());
+    });
+
+    // requests are answered during the realm update
+    while !lost_realm.is_paused() {
+        lost_realm.update();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    lost_realm.add_destiny_rift_event(Collision(true));
+    client.join().unwrap();
+}
@@ -0,0 +1,55 @@
+use prime_derived::{hierarchy_ethereal_flow, EtherealFlowArcaneScript};
+use prime_forge::{
+    arcane_weft::ArcaneWeft,
+    declare_arcane_weft,
+    forged_trait::ForgedTrait,
+    lost_realm::LostRealm,
+    soul_thread::{wait_frames, with_lost_realm},
+};
+
+#[hierarchy_ethereal_flow]
+#[derive(Default)]
+pub struct Spinner {
+    #[runic]
+    pub speed: f32,
+}
+
+impl ForgedTrait for Spinner {
+    fn update(&mut self, _lost_realm: &mut LostRealm, _dt: f32) {
+        println!("Spinner spinning at {}", self.speed);
+    }
+}
+
+pub struct SpinnerWeft;
+
+impl ArcaneWeft for SpinnerWeft {
+    fn name(&self) -> &str {
+        "spinner"
+    }
+
+    // `craft` takes `&mut self` since the weft is kept until `cleanup`
+    fn craft(&mut self, lost_realm: &mut LostRealm) {
+        lost_realm
+            .forge_new_object(
+                "Spinner",
+                (Spinner {
+                    speed: 1.0,
+                    ..Default::default()
+                },),
+            )
+            .unwrap();
+        // soul threads of the plugin wait on the host's updates
+        lost_realm.spawn_soul_thread("spinner_warmup", async {
+            wait_frames(1).await;
+            let spinners =
+                with_lost_realm(|lost_realm| lost_realm.get_all_trait_by_type::<Spinner>().len());
+            println!("Spinners warmed up: {:?}", spinners);
+        });
+    }
+
+    fn cleanup(&mut self, _lost_realm: &mut LostRealm) {
+        println!("Spinner weft cleaned up");
+    }
+}
+
+declare_arcane_weft!(SpinnerWeft);
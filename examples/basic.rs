@@ -1,6 +1,10 @@
 use prime_derived::{hierarchy_ethereal_flow, DestinyRiftArcaneScript, EtherealFlowArcaneScript};
 use prime_forge::{
-    arcane_weft::ArcaneWeft, forged_trait::ForgedTrait, lost_realm::LostRealm, soul_thread::{EssenceAspect, SoulThread, TemporalPause}
+    arcane_weft::ArcaneWeft,
+    divination::With,
+    forged_trait::ForgedTrait,
+    lost_realm::LostRealm,
+    soul_thread::{EssenceAspect, SoulThread, TemporalPause},
 };
 
 #[hierarchy_ethereal_flow]
@@ -12,7 +16,9 @@ struct Player {
 
 impl ForgedTrait for Player {
     fn start(&mut self, lost_realm: &mut LostRealm) {
-        lost_realm.forge_new_object("Player", (Player::default(), Health::default())).unwrap();
+        lost_realm
+            .forge_new_object("Player", (Player::default(), Health::default()))
+            .unwrap();
     }
 
     fn update(&mut self, _lost_realm: &mut LostRealm, _dt: f32) {
@@ -23,22 +29,23 @@ impl ForgedTrait for Player {
 #[hierarchy_ethereal_flow]
 #[derive(Default)]
 pub struct Health {
+    #[runic]
     pub health: i32,
 }
 
-impl ForgedTrait for Health {
-   
-}
+impl ForgedTrait for Health {}
 
 #[derive(DestinyRiftArcaneScript, EtherealFlowArcaneScript)]
 pub struct Collision(bool);
 
-
 pub struct ArcaneWeftCreation;
-impl ArcaneWeft for ArcaneWeftCreation{
-    fn craft(self, lost_realm: &mut LostRealm) {
+impl ArcaneWeft for ArcaneWeftCreation {
+    // `craft` takes `&mut self` since the weft is kept until `cleanup`
+    fn craft(&mut self, lost_realm: &mut LostRealm) {
         lost_realm.add_destiny_rift_event(Collision(true));
-        lost_realm.forge_new_object("Player", (Player::default(), Health::default())).unwrap();
+        lost_realm
+            .forge_new_object("Player", (Player::default(), Health::default()))
+            .unwrap();
         println!("Arcane Weft Creation")
     }
 }
@@ -67,7 +74,7 @@ fn main() {
     f.transform.borrow_mut().position += glm::vec3(1.0, 0.0, 0.0);
     f2.transform.borrow_mut().position += glm::vec3(1.0, 0.0, 0.0);
 
-    f.set_transform_parent(f2.transform.clone());
+    f.set_transform_parent(f2.transform.clone()).unwrap();
     f.transform.borrow_mut().update_self_and_children();
 
     let h = f.get_trait::<Health>().unwrap();
@@ -79,7 +86,8 @@ fn main() {
     let father = lost_realm.get_mut_parent_forged_object(p).unwrap();
     println!("Father: {:?}", father.name);
 
-    let all_forged_object_by_health_traits = lost_realm.get_mut_all_forged_objects_by_trait::<Health>();
+    let all_forged_object_by_health_traits =
+        lost_realm.get_mut_all_forged_objects_by_trait::<Health>();
     for fo in all_forged_object_by_health_traits {
         println!("Forged Object: {:?}", fo.name);
         fo.name += "!";
@@ -101,11 +109,25 @@ fn main() {
         println!("Health: {:?}", health.health);
     }
 
-    
+    for (fo, (health, player)) in &mut lost_realm
+        .divine::<(&Health, Option<&Player>)>()
+        .without::<Player>()
+        .or::<(With<Health>,)>()
+    {
+        println!(
+            "Divined: {:?} {:?} {:?}",
+            fo.name,
+            health.health,
+            player.is_some()
+        );
+    }
 
     lost_realm.add_destiny_rift_event(Collision(true));
-    let rs = lost_realm.consume_destiny_rift_event::<Collision>();
-    if let Some(rs) = rs {
+    println!(
+        "Pending collisions: {}",
+        lost_realm.count_destiny_rift_events::<Collision>()
+    );
+    if let Some(rs) = lost_realm.take_destiny_rift_event::<Collision>() {
         println!("Collision: {:?}", rs.0);
     }
     let mut counter = 10;
@@ -116,19 +138,19 @@ fn main() {
             println!("Soul thread finished");
             return EssenceAspect::Finished;
         }
-        return EssenceAspect::Yielded(TemporalPause {
+        EssenceAspect::Yielded(TemporalPause {
             amount_in_seconds: 1.0,
-        });
+        })
     }));
 
-    lost_realm.arcane_weft_craft(ArcaneWeftCreation);
+    lost_realm.arcane_weft_craft(ArcaneWeftCreation).unwrap();
     let dt = lost_realm.get_delta_time();
     let time_since_start = lost_realm.get_time_elapsed();
     println!("Delta Time: {:?}", dt);
     println!("Time Since Start: {:?}", time_since_start);
 
-    
+    println!("{}", lost_realm.dump());
+
     lost_realm.start();
     lost_realm.debug_update();
 }
-
@@ -0,0 +1,22 @@
+use prime_forge::lost_realm::LostRealm;
+
+// build the plugin first with `cargo build --example arcane_weft_plugin --features hot_reload`
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "target/debug/examples/libarcane_weft_plugin.so".to_string());
+    let mut lost_realm = LostRealm::new();
+
+    lost_realm.load_arcane_weft_library(&path).unwrap();
+    lost_realm.start();
+    lost_realm.update();
+
+    // rebuilding the plugin while this runs is picked up by reload_changed_arcane_weft_libraries
+    lost_realm.reload_arcane_weft_library(&path).unwrap();
+    lost_realm.update();
+    print!("{}", lost_realm.dump());
+
+    lost_realm.unload_arcane_weft_library(&path).unwrap();
+    lost_realm.update();
+    print!("{}", lost_realm.dump());
+}
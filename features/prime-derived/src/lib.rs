@@ -16,7 +16,7 @@ fn impl_destiny_rift(ast: &syn::DeriveInput) -> TokenStream {
     gen.into()
 }
 
-#[proc_macro_derive(EtherealFlowArcaneScript)]
+#[proc_macro_derive(EtherealFlowArcaneScript, attributes(runic))]
 pub fn ethereal_flow_derive(input: TokenStream) -> TokenStream {
     let ast = syn::parse(input).unwrap();
     impl_ethereal_flow(&ast)
@@ -24,6 +24,7 @@ pub fn ethereal_flow_derive(input: TokenStream) -> TokenStream {
 
 fn impl_ethereal_flow(ast: &syn::DeriveInput) -> TokenStream {
     let name = &ast.ident;
+    let runic = impl_runic_fields(ast);
     let gen = quote! {
         impl forged_in_lost_lands::EtherealFlow for #name {
             fn as_any(&self) -> &dyn std::any::Any {
@@ -33,11 +34,62 @@ fn impl_ethereal_flow(ast: &syn::DeriveInput) -> TokenStream {
             fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
                 self
             }
+
+            #runic
         }
     };
     gen.into()
 }
 
+// reflection for the fields marked with #[runic]
+fn impl_runic_fields(ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
+    let syn::Data::Struct(data) = &ast.data else {
+        return quote! {};
+    };
+    let (members, names): (Vec<_>, Vec<_>) = data
+        .fields
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| field.attrs.iter().any(|attr| attr.path().is_ident("runic")))
+        .map(|(index, field)| match &field.ident {
+            Some(ident) => (syn::Member::Named(ident.clone()), ident.to_string()),
+            None => (syn::Member::Unnamed(index.into()), index.to_string()),
+        })
+        .unzip();
+    if members.is_empty() {
+        return quote! {};
+    }
+
+    quote! {
+        fn runic_fields(&self) -> Vec<forged_in_lost_lands::runic_essence::RunicField> {
+            vec![#(forged_in_lost_lands::runic_essence::RunicField::new(
+                #names,
+                forged_in_lost_lands::runic_essence::RunicEssence::to_runic(&self.#members),
+            )),*]
+        }
+
+        fn set_runic_field(
+            &mut self,
+            name: &str,
+            value: forged_in_lost_lands::runic_essence::RunicValue,
+        ) -> Result<(), forged_in_lost_lands::lost_lands_fault::LostLostLandsFaultRunicEssence> {
+            match name {
+                #(#names => {
+                    self.#members = forged_in_lost_lands::runic_essence::RunicEssence::from_runic(&value)
+                        .ok_or_else(|| forged_in_lost_lands::lost_lands_fault::LostLostLandsFaultRunicEssence::FieldTypeMismatch(
+                            name.to_string(),
+                            value.kind().to_string(),
+                        ))?;
+                    Ok(())
+                })*
+                _ => Err(forged_in_lost_lands::lost_lands_fault::LostLostLandsFaultRunicEssence::FieldNotFound(
+                    name.to_string(),
+                )),
+            }
+        }
+    }
+}
+
 #[proc_macro_attribute]
 pub fn hierarchy_ethereal_flow(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let struct_pos = item.to_string().find("struct").unwrap();
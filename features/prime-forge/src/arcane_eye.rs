@@ -0,0 +1,414 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender, SyncSender, TrySendError},
+        Arc,
+    },
+    thread,
+};
+
+use serde_json::{json, Map, Number, Value};
+
+use crate::{
+    forged_object::ForgedObject,
+    lost_realm::LostRealm,
+    omen::{foretell, OmenCategory, OmenLevel},
+    runic_essence::{RunicField, RunicValue},
+};
+
+enum EyeMessage {
+    Connected(usize, TcpStream),
+    Line(usize, String),
+    Disconnected(usize),
+}
+
+// lines waiting to be written to a client before new ones are dropped
+const OUTBOX_CAPACITY: usize = 256;
+
+struct EyeClient {
+    stream: TcpStream,
+    // written by a thread of its own, so a client that stops reading never blocks the update
+    outbox: SyncSender<String>,
+    watching_rifts: bool,
+}
+
+impl EyeClient {
+    fn new(stream: TcpStream) -> EyeClient {
+        let (outbox, lines) = mpsc::sync_channel::<String>(OUTBOX_CAPACITY);
+        if let Ok(mut writer) = stream.try_clone() {
+            thread::spawn(move || {
+                for line in lines {
+                    if writeln!(writer, "{}", line).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        EyeClient {
+            stream,
+            outbox,
+            watching_rifts: false,
+        }
+    }
+
+    // false once the client is gone
+    fn send(&self, client: usize, line: String) -> bool {
+        match self.outbox.try_send(line) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                foretell(OmenLevel::Warn, OmenCategory::Realm, || {
+                    format!("Arcane eye client {} is not reading, reply dropped", client)
+                });
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+// alias for the remote inspector
+/// Serves a line-delimited JSON protocol on localhost.
+/// Requests look like `{"id":1,"cmd":"list_objects"}` and are answered on the next realm update.
+/// Commands: list_objects, read_object, read_trait, write_field, dump, pause, resume, step,
+/// watch_rifts and unwatch_rifts.
+pub struct ArcaneEye {
+    address: SocketAddr,
+    receiver: Receiver<EyeMessage>,
+    clients: HashMap<usize, EyeClient>,
+    closing: Arc<AtomicBool>,
+}
+
+impl ArcaneEye {
+    /// Binds `127.0.0.1:port`, pass 0 to let the system pick a free port
+    pub fn open(port: u16) -> std::io::Result<ArcaneEye> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        let address = listener.local_addr()?;
+        let (sender, receiver) = mpsc::channel();
+        let closing = Arc::new(AtomicBool::new(false));
+        let accept_closing = closing.clone();
+        thread::spawn(move || accept_clients(listener, sender, accept_closing));
+
+        Ok(ArcaneEye {
+            address,
+            receiver,
+            clients: HashMap::new(),
+            closing,
+        })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    pub fn client_count(&self) -> usize {
+        self.clients.len()
+    }
+
+    // returns the request lines received since the last call
+    pub(crate) fn gaze(&mut self) -> Vec<(usize, String)> {
+        let mut requests = Vec::new();
+        while let Ok(message) = self.receiver.try_recv() {
+            match message {
+                EyeMessage::Connected(client, stream) => {
+                    self.clients.insert(client, EyeClient::new(stream));
+                }
+                EyeMessage::Line(client, line) => requests.push((client, line)),
+                EyeMessage::Disconnected(client) => {
+                    self.clients.remove(&client);
+                }
+            }
+        }
+        requests
+    }
+
+    pub(crate) fn reply(&mut self, client: usize, response: &Value) {
+        let sent = self
+            .clients
+            .get(&client)
+            .map(|eye_client| eye_client.send(client, response.to_string()));
+        if sent == Some(false) {
+            self.clients.remove(&client);
+        }
+    }
+
+    pub(crate) fn watch_rifts(&mut self, client: usize, watching: bool) {
+        if let Some(eye_client) = self.clients.get_mut(&client) {
+            eye_client.watching_rifts = watching;
+        }
+    }
+
+    pub(crate) fn publish_rift(&mut self, name: &str, fields: &[RunicField]) {
+        if !self.clients.values().any(|client| client.watching_rifts) {
+            return;
+        }
+        let notification = json!({
+            "event": "destiny_rift",
+            "name": name,
+            "fields": fields_to_json(fields),
+        });
+        let notification = notification.to_string();
+        self.clients
+            .retain(|id, client| !client.watching_rifts || client.send(*id, notification.clone()));
+    }
+}
+
+impl Drop for ArcaneEye {
+    fn drop(&mut self) {
+        self.closing.store(true, Ordering::SeqCst);
+        for client in self.clients.values() {
+            let _ = client.stream.shutdown(Shutdown::Both);
+        }
+        // wake up the accepting thread so it notices the eye is closing
+        let _ = TcpStream::connect(self.address);
+    }
+}
+
+fn accept_clients(listener: TcpListener, sender: Sender<EyeMessage>, closing: Arc<AtomicBool>) {
+    for (client, stream) in listener.incoming().enumerate() {
+        if closing.load(Ordering::SeqCst) {
+            return;
+        }
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let Ok(writer) = stream.try_clone() else {
+            continue;
+        };
+        if sender.send(EyeMessage::Connected(client, writer)).is_err() {
+            return;
+        }
+        let sender = sender.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(stream).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                if sender.send(EyeMessage::Line(client, line)).is_err() {
+                    return;
+                }
+            }
+            let _ = sender.send(EyeMessage::Disconnected(client));
+        });
+    }
+}
+
+pub(crate) fn answer(lost_realm: &mut LostRealm, client: usize, line: &str) -> Value {
+    let request = match serde_json::from_str::<Value>(line) {
+        Ok(request) => request,
+        Err(err) => return json!({ "ok": false, "error": err.to_string() }),
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    match answer_request(lost_realm, client, &request) {
+        Ok(result) => json!({ "id": id, "ok": true, "result": result }),
+        Err(error) => json!({ "id": id, "ok": false, "error": error }),
+    }
+}
+
+fn answer_request(
+    lost_realm: &mut LostRealm,
+    client: usize,
+    request: &Value,
+) -> Result<Value, String> {
+    let cmd = request
+        .get("cmd")
+        .and_then(Value::as_str)
+        .ok_or("missing cmd")?;
+    match cmd {
+        "list_objects" => Ok(Value::Array(
+            lost_realm
+                .divine::<()>()
+                .iter()
+                .map(|(object, _)| {
+                    json!({
+                        "name": object.name,
+                        "id": object.id.to_string(),
+                        "traits": object
+                            .forged_traits
+                            .iter()
+                            .map(|forged_trait| forged_trait.borrow().trait_name())
+                            .collect::<Vec<_>>(),
+                    })
+                })
+                .collect(),
+        )),
+        "read_object" => {
+            let object = find_object(lost_realm, request)?;
+            Ok(json!({
+                "name": object.name,
+                "id": object.id.to_string(),
+                "traits": object
+                    .forged_traits
+                    .iter()
+                    .map(|forged_trait| {
+                        let forged_trait = forged_trait.borrow();
+                        json!({
+                            "name": forged_trait.trait_name(),
+                            "fields": fields_to_json(&forged_trait.runic_fields()),
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+            }))
+        }
+        "read_trait" => {
+            let object = find_object(lost_realm, request)?;
+            let forged_trait = find_trait(object, request)?.borrow();
+            Ok(json!({
+                "name": forged_trait.trait_name(),
+                "fields": fields_to_json(&forged_trait.runic_fields()),
+            }))
+        }
+        "write_field" => {
+            let object = find_object(lost_realm, request)?;
+            let forged_trait = find_trait(object, request)?;
+            let field = request
+                .get("field")
+                .and_then(Value::as_str)
+                .ok_or("missing field")?;
+            let value = request.get("value").ok_or("missing value")?;
+            forged_trait
+                .borrow_mut()
+                .set_runic_field(field, json_to_runic(value)?)
+                .map_err(|err| err.to_string())?;
+            Ok(Value::Null)
+        }
+        "dump" => serde_json::from_str(&lost_realm.dump().to_json()).map_err(|err| err.to_string()),
+        "pause" => {
+            lost_realm.pause();
+            Ok(Value::Null)
+        }
+        "resume" => {
+            lost_realm.resume();
+            Ok(Value::Null)
+        }
+        "step" => {
+            let frames = request.get("frames").and_then(Value::as_u64).unwrap_or(1);
+            let frames =
+                u32::try_from(frames).map_err(|_| format!("too many frames {}", frames))?;
+            lost_realm.step(frames);
+            Ok(Value::Null)
+        }
+        "watch_rifts" | "unwatch_rifts" => {
+            lost_realm.watch_arcane_eye_rifts(client, cmd == "watch_rifts");
+            Ok(Value::Null)
+        }
+        cmd => Err(format!("unknown cmd {}", cmd)),
+    }
+}
+
+fn find_object<'a>(lost_realm: &'a LostRealm, request: &Value) -> Result<&'a ForgedObject, String> {
+    let object = request
+        .get("object")
+        .and_then(Value::as_str)
+        .ok_or("missing object")?;
+    let found = match uuid::Uuid::parse_str(object) {
+        Ok(id) => lost_realm.get_forged_object_by_id(id),
+        Err(_) => lost_realm.get_forged_object(object),
+    };
+    found.ok_or_else(|| format!("object {} not found", object))
+}
+
+fn find_trait<'a>(
+    object: &'a ForgedObject,
+    request: &Value,
+) -> Result<&'a std::cell::RefCell<dyn crate::forged_trait::ForgedTrait>, String> {
+    let name = request
+        .get("trait")
+        .and_then(Value::as_str)
+        .ok_or("missing trait")?;
+    object
+        .forged_traits
+        .iter()
+        .find(|forged_trait| {
+            let trait_name = forged_trait.borrow().trait_name();
+            trait_name == name || trait_name.rsplit("::").next() == Some(name)
+        })
+        .map(|forged_trait| forged_trait.as_ref())
+        .ok_or_else(|| format!("trait {} not found on {}", name, object.name))
+}
+
+fn fields_to_json(fields: &[RunicField]) -> Value {
+    Value::Object(
+        fields
+            .iter()
+            .map(|field| (field.name.to_string(), runic_to_json(&field.value)))
+            .collect::<Map<_, _>>(),
+    )
+}
+
+fn runic_to_json(value: &RunicValue) -> Value {
+    match value {
+        RunicValue::Empty => Value::Null,
+        RunicValue::Bool(value) => Value::Bool(*value),
+        RunicValue::Int(value) => Value::from(*value),
+        RunicValue::Float(value) => Number::from_f64(*value).map_or(Value::Null, Value::Number),
+        RunicValue::Text(value) => Value::String(value.clone()),
+        RunicValue::Vector(values) => values.iter().map(|value| *value as f64).collect(),
+    }
+}
+
+fn json_to_runic(value: &Value) -> Result<RunicValue, String> {
+    match value {
+        Value::Null => Ok(RunicValue::Empty),
+        Value::Bool(value) => Ok(RunicValue::Bool(*value)),
+        Value::Number(number) => Ok(number
+            .as_i64()
+            .map(RunicValue::Int)
+            .or_else(|| number.as_f64().map(RunicValue::Float))
+            .unwrap_or(RunicValue::Empty)),
+        Value::String(value) => Ok(RunicValue::Text(value.clone())),
+        Value::Array(values) => values
+            .iter()
+            .map(|value| value.as_f64().map(|value| value as f32))
+            .collect::<Option<Vec<_>>>()
+            .map(RunicValue::Vector)
+            .ok_or_else(|| "vectors can only hold numbers".to_string()),
+        Value::Object(_) => Err("objects can not be written to a runic field".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_rejects_frames_beyond_u32() {
+        let mut lost_realm = LostRealm::new();
+        let response = answer(
+            &mut lost_realm,
+            0,
+            r#"{"id":1,"cmd":"step","frames":5000000000}"#,
+        );
+        assert_eq!(response["ok"], Value::Bool(false));
+        let response = answer(
+            &mut lost_realm,
+            0,
+            r#"{"id":2,"cmd":"step","frames":4294967295}"#,
+        );
+        assert_eq!(response["ok"], Value::Bool(true));
+        let response = answer(&mut lost_realm, 0, r#"{"id":3,"cmd":"step","frames":1}"#);
+        assert_eq!(response["ok"], Value::Bool(true));
+    }
+
+    #[test]
+    fn a_client_that_stops_reading_does_not_block_replies() {
+        let mut eye = ArcaneEye::open(0).unwrap();
+        let _client = TcpStream::connect(eye.address()).unwrap();
+        while eye.client_count() == 0 {
+            eye.gaze();
+            thread::yield_now();
+        }
+        let client = *eye.clients.keys().next().unwrap();
+        // far more than the socket buffers hold
+        let response = Value::String("x".repeat(64 * 1024));
+        for _ in 0..OUTBOX_CAPACITY * 4 {
+            eye.reply(client, &response);
+        }
+        assert_eq!(eye.client_count(), 1);
+    }
+}
@@ -1,5 +1,328 @@
-use crate::lost_realm::LostRealm;
+use std::{
+    ffi::c_void,
+    sync::atomic::{AtomicPtr, Ordering},
+};
 
-pub trait ArcaneWeft {
-    fn craft(self, lost_realm: &mut LostRealm);
+use crate::{lost_lands_fault::LostLostLandsFaultArcaneWeft, lost_realm::LostRealm};
+
+/// Bumped whenever the layout shared with `cdylib` plugins changes
+pub const ARCANE_WEFT_ABI_VERSION: u32 = 4;
+
+/// Plugins are only loaded when built against the same engine version and features as the host
+pub const ARCANE_WEFT_ABI: u64 = ((ARCANE_WEFT_ABI_VERSION as u64) << 32)
+    | ((std::mem::size_of::<LostRealm>() as u64) << 8)
+    | (cfg!(feature = "profiling") as u64)
+    | ((cfg!(feature = "inspector") as u64) << 1)
+    | ((cfg!(feature = "hot_reload") as u64) << 2);
+
+// alias for plugins
+/// Wefts are crafted after the wefts named in `dependencies`, then every weft of the batch is finished.
+/// `cleanup` runs when the weft is removed or the realm is dropped.
+pub trait ArcaneWeft: std::any::Any {
+    fn craft(&mut self, lost_realm: &mut LostRealm);
+
+    /// Unique name other wefts refer to in their dependencies
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    fn version(&self) -> &str {
+        "0.0.0"
+    }
+
+    /// Names of the wefts that must be crafted before this one
+    fn dependencies(&self) -> Vec<&str> {
+        Vec::new()
+    }
+
+    /// Runs once every weft crafted with this one is ready
+    #[allow(unused_variables)]
+    fn finish(&mut self, lost_realm: &mut LostRealm) {}
+
+    #[allow(unused_variables)]
+    fn cleanup(&mut self, lost_realm: &mut LostRealm) {}
+}
+
+/// What a crafted weft declared about itself
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArcaneWeftInfo {
+    name: String,
+    version: String,
+    dependencies: Vec<String>,
+}
+
+impl ArcaneWeftInfo {
+    pub(crate) fn of(arcane_weft: &dyn ArcaneWeft) -> ArcaneWeftInfo {
+        ArcaneWeftInfo {
+            name: arcane_weft.name().to_string(),
+            version: arcane_weft.version().to_string(),
+            dependencies: arcane_weft
+                .dependencies()
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_version(&self) -> &str {
+        &self.version
+    }
+
+    pub fn get_dependencies(&self) -> &[String] {
+        &self.dependencies
+    }
+
+    pub fn depends_on(&self, name: &str) -> bool {
+        self.dependencies
+            .iter()
+            .any(|dependency| dependency == name)
+    }
+}
+
+// a crafted weft, library wefts stay owned by their library
+pub(crate) struct ArcaneWeftEntry {
+    pub(crate) info: ArcaneWeftInfo,
+    pub(crate) arcane_weft: Option<Box<dyn ArcaneWeft>>,
+    #[cfg(feature = "hot_reload")]
+    pub(crate) library: Option<std::path::PathBuf>,
+}
+
+/// Orders wefts so each comes after its dependencies, keeping the insertion order otherwise.
+/// Dependencies must either be in `crafted` or in the batch itself.
+pub(crate) fn weave_order(
+    crafted: &[ArcaneWeftEntry],
+    batch: &[ArcaneWeftInfo],
+) -> Result<Vec<usize>, LostLostLandsFaultArcaneWeft> {
+    for info in batch {
+        for dependency in info.dependencies.iter() {
+            let known = crafted.iter().any(|entry| &entry.info.name == dependency)
+                || batch.iter().any(|other| &other.name == dependency);
+            if !known {
+                return Err(LostLostLandsFaultArcaneWeft::MissingDependency(
+                    info.name.clone(),
+                    dependency.clone(),
+                ));
+            }
+        }
+    }
+
+    let mut order = Vec::with_capacity(batch.len());
+    let mut woven = vec![false; batch.len()];
+    while order.len() < batch.len() {
+        let ready = (0..batch.len()).find(|index| {
+            !woven[*index]
+                && batch[*index].dependencies.iter().all(|dependency| {
+                    batch
+                        .iter()
+                        .enumerate()
+                        .all(|(other, info)| &info.name != dependency || woven[other])
+                })
+        });
+        let Some(ready) = ready else {
+            let cycle = (0..batch.len())
+                .filter(|index| !woven[*index])
+                .map(|index| batch[index].name.clone())
+                .collect();
+            return Err(LostLostLandsFaultArcaneWeft::DependencyCycle(cycle));
+        };
+        woven[ready] = true;
+        order.push(ready);
+    }
+    Ok(order)
+}
+
+/// Thread locals of the host, reached by plugins since each one links its own copy of the engine.
+/// Each function returns the value of the calling thread.
+#[doc(hidden)]
+pub struct HostBindings {
+    pub(crate) soul_context: extern "C" fn() -> *const c_void,
+    pub(crate) oracle: extern "C" fn() -> *const c_void,
+}
+
+// what the host hands to the plugins it loads
+#[cfg(feature = "hot_reload")]
+pub(crate) static THREAD_BINDINGS: HostBindings = HostBindings {
+    soul_context: crate::soul_thread::local_soul_context,
+    oracle: crate::omen::local_oracle,
+};
+
+// set inside a plugin once the host loaded it, null in the host
+static HOST_BINDINGS: AtomicPtr<HostBindings> = AtomicPtr::new(std::ptr::null_mut());
+
+pub(crate) fn host_bindings() -> Option<&'static HostBindings> {
+    // the host bindings are a static of the host, which outlives its plugins
+    unsafe { HOST_BINDINGS.load(Ordering::Acquire).as_ref() }
+}
+
+/// Called by `declare_arcane_weft!` when the host loads the plugin
+///
+/// # Safety
+/// `bindings` must point to the `HostBindings` of a host built like the plugin
+#[doc(hidden)]
+pub unsafe fn bind_host(bindings: *const c_void) {
+    HOST_BINDINGS.store(bindings as *mut HostBindings, Ordering::Release);
+}
+
+/// Exports the C-ABI entry points that let a `cdylib` be loaded with
+/// `LostRealm::load_arcane_weft_library`.
+/// The plugin must be built with the same compiler and engine version as the host.
+#[macro_export]
+macro_rules! declare_arcane_weft {
+    ($arcane_weft: expr) => {
+        #[no_mangle]
+        pub extern "C" fn arcane_weft_abi() -> u64 {
+            $crate::arcane_weft::ARCANE_WEFT_ABI
+        }
+
+        /// # Safety
+        /// Called by the host with its `HostBindings`
+        #[no_mangle]
+        pub unsafe extern "C" fn arcane_weft_bind(host: *const ::std::ffi::c_void) {
+            $crate::arcane_weft::bind_host(host)
+        }
+
+        #[no_mangle]
+        pub extern "C" fn arcane_weft_entry() -> *mut ::std::ffi::c_void {
+            let arcane_weft: Box<dyn $crate::arcane_weft::ArcaneWeft> = Box::new($arcane_weft);
+            Box::into_raw(Box::new(arcane_weft)) as *mut ::std::ffi::c_void
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    struct Rune {
+        name: &'static str,
+        dependencies: Vec<&'static str>,
+        log: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl ArcaneWeft for Rune {
+        fn craft(&mut self, _lost_realm: &mut LostRealm) {
+            self.log.borrow_mut().push(format!("craft {}", self.name));
+        }
+
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn dependencies(&self) -> Vec<&str> {
+            self.dependencies.clone()
+        }
+
+        fn finish(&mut self, _lost_realm: &mut LostRealm) {
+            self.log.borrow_mut().push(format!("finish {}", self.name));
+        }
+    }
+
+    fn rune(
+        log: &Rc<RefCell<Vec<String>>>,
+        name: &'static str,
+        dependencies: &[&'static str],
+    ) -> Rune {
+        Rune {
+            name,
+            dependencies: dependencies.to_vec(),
+            log: log.clone(),
+        }
+    }
+
+    #[test]
+    fn wefts_are_crafted_after_their_dependencies_then_finished() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut lost_realm = LostRealm::new();
+        lost_realm
+            .add_arcane_weft(rune(&log, "roof", &["walls"]))
+            .unwrap();
+        lost_realm
+            .add_arcane_weft(rune(&log, "walls", &["ground"]))
+            .unwrap();
+        lost_realm
+            .add_arcane_weft(rune(&log, "ground", &[]))
+            .unwrap();
+        lost_realm.add_arcane_weft(rune(&log, "sky", &[])).unwrap();
+        lost_realm.craft_arcane_wefts().unwrap();
+        let expected = [
+            "craft ground",
+            "craft walls",
+            "craft roof",
+            "craft sky",
+            "finish ground",
+            "finish walls",
+            "finish roof",
+            "finish sky",
+        ];
+        assert_eq!(*log.borrow(), expected);
+        lost_realm
+            .arcane_weft_craft(rune(&log, "door", &["walls"]))
+            .unwrap();
+        assert!(lost_realm
+            .get_arcane_weft("door")
+            .unwrap()
+            .depends_on("walls"));
+    }
+
+    #[test]
+    fn missing_dependency_crafts_nothing() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut lost_realm = LostRealm::new();
+        lost_realm
+            .add_arcane_weft(rune(&log, "ground", &[]))
+            .unwrap();
+        lost_realm
+            .add_arcane_weft(rune(&log, "roof", &["walls"]))
+            .unwrap();
+        let fault = lost_realm.craft_arcane_wefts().unwrap_err();
+        assert!(matches!(
+            fault,
+            LostLostLandsFaultArcaneWeft::MissingDependency(weft, dependency)
+                if weft == "roof" && dependency == "walls"
+        ));
+        assert!(log.borrow().is_empty());
+        assert!(!lost_realm.has_arcane_weft("ground"));
+    }
+
+    #[test]
+    fn dependency_cycle_is_reported() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut lost_realm = LostRealm::new();
+        lost_realm
+            .add_arcane_weft(rune(&log, "ground", &[]))
+            .unwrap();
+        lost_realm
+            .add_arcane_weft(rune(&log, "egg", &["hen"]))
+            .unwrap();
+        lost_realm
+            .add_arcane_weft(rune(&log, "hen", &["egg"]))
+            .unwrap();
+        let fault = lost_realm.craft_arcane_wefts().unwrap_err();
+        assert!(matches!(
+            fault,
+            LostLostLandsFaultArcaneWeft::DependencyCycle(cycle) if cycle == ["egg", "hen"]
+        ));
+        assert!(log.borrow().is_empty());
+    }
+
+    #[test]
+    fn duplicate_weft_is_rejected() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut lost_realm = LostRealm::new();
+        lost_realm
+            .arcane_weft_craft(rune(&log, "ground", &[]))
+            .unwrap();
+        let fault = lost_realm
+            .add_arcane_weft(rune(&log, "ground", &[]))
+            .unwrap_err();
+        assert!(
+            matches!(fault, LostLostLandsFaultArcaneWeft::DuplicateArcaneWeft(name) if name == "ground")
+        );
+    }
 }
@@ -0,0 +1,246 @@
+use std::{
+    ffi::c_void,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::SystemTime,
+};
+
+use crate::{
+    arcane_weft::{ArcaneWeft, ARCANE_WEFT_ABI, THREAD_BINDINGS},
+    lost_lands_fault::LostLostLandsFaultArcaneWeft,
+};
+
+static SHADOW_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Address ranges the dynamic loader mapped for a library
+pub(crate) struct LibraryImage {
+    ranges: Vec<(usize, usize)>,
+}
+
+impl LibraryImage {
+    fn of(path: &Path) -> LibraryImage {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let maps = std::fs::read_to_string("/proc/self/maps").unwrap_or_default();
+        let ranges = maps
+            .lines()
+            .filter_map(|line| {
+                let mut columns = line.split_whitespace();
+                let range = columns.next()?;
+                let mapped = columns.nth(4)?;
+                if Path::new(mapped) != path {
+                    return None;
+                }
+                let (start, end) = range.split_once('-')?;
+                Some((
+                    usize::from_str_radix(start, 16).ok()?,
+                    usize::from_str_radix(end, 16).ok()?,
+                ))
+            })
+            .collect();
+        LibraryImage { ranges }
+    }
+
+    /// Whether the vtable behind a trait object was emitted by this library
+    pub(crate) fn owns<T: ?Sized>(&self, ptr: *const T) -> bool {
+        if std::mem::size_of::<*const T>() != 2 * std::mem::size_of::<usize>() {
+            return false;
+        }
+        // trait object pointers are laid out as (data, vtable)
+        let (_, vtable) = unsafe { std::mem::transmute_copy::<*const T, (usize, usize)>(&ptr) };
+        self.ranges
+            .iter()
+            .any(|(start, end)| (*start..*end).contains(&vtable))
+    }
+}
+
+// alias for a hot reloadable plugin library
+pub struct ArcaneWeftLibrary {
+    path: PathBuf,
+    shadow_path: PathBuf,
+    modified: Option<SystemTime>,
+    image: LibraryImage,
+    pub(crate) forged_objects: Vec<uuid::Uuid>,
+    pub(crate) arcane_weft: Option<Box<dyn ArcaneWeft>>,
+    library: Option<libloading::Library>,
+}
+
+impl ArcaneWeftLibrary {
+    pub(crate) fn open(path: &Path) -> Result<ArcaneWeftLibrary, LostLostLandsFaultArcaneWeft> {
+        let display = path.display().to_string();
+        let load_fault = |err: &dyn std::fmt::Display| {
+            LostLostLandsFaultArcaneWeft::LibraryLoad(display.clone(), err.to_string())
+        };
+        let modified = std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+
+        // load a copy so the original can be rebuilt, and the loader never hands back a stale handle
+        let file_name = path.file_name().ok_or_else(|| load_fault(&"not a file"))?;
+        let shadow_path = std::env::temp_dir().join(format!(
+            "arcane-weft-{}-{}-{}",
+            std::process::id(),
+            SHADOW_COUNTER.fetch_add(1, Ordering::SeqCst),
+            file_name.to_string_lossy()
+        ));
+        std::fs::copy(path, &shadow_path).map_err(|err| load_fault(&err))?;
+
+        let library = match unsafe { libloading::Library::new(&shadow_path) } {
+            Ok(library) => library,
+            Err(err) => {
+                let _ = std::fs::remove_file(&shadow_path);
+                return Err(load_fault(&err));
+            }
+        };
+        let mut arcane_weft_library = ArcaneWeftLibrary {
+            path: path.to_path_buf(),
+            image: LibraryImage::of(&shadow_path),
+            shadow_path,
+            modified,
+            forged_objects: Vec::new(),
+            arcane_weft: None,
+            library: Some(library),
+        };
+        arcane_weft_library.arcane_weft = Some(arcane_weft_library.entry()?);
+        Ok(arcane_weft_library)
+    }
+
+    fn entry(&self) -> Result<Box<dyn ArcaneWeft>, LostLostLandsFaultArcaneWeft> {
+        let library = self.library.as_ref().expect("library is loaded");
+        let symbol_fault = |symbol: &str| {
+            LostLostLandsFaultArcaneWeft::SymbolNotFound(
+                self.path.display().to_string(),
+                symbol.to_string(),
+            )
+        };
+        unsafe {
+            let abi = library
+                .get::<extern "C" fn() -> u64>(b"arcane_weft_abi")
+                .map_err(|_| symbol_fault("arcane_weft_abi"))?;
+            let abi = abi();
+            if abi != ARCANE_WEFT_ABI {
+                return Err(LostLostLandsFaultArcaneWeft::AbiMismatch(
+                    self.path.display().to_string(),
+                    abi,
+                ));
+            }
+            // soul threads and omens of the plugin use the host's thread locals
+            let bind = library
+                .get::<unsafe extern "C" fn(*const c_void)>(b"arcane_weft_bind")
+                .map_err(|_| symbol_fault("arcane_weft_bind"))?;
+            bind(&THREAD_BINDINGS as *const _ as *const c_void);
+            let entry = library
+                .get::<extern "C" fn() -> *mut c_void>(b"arcane_weft_entry")
+                .map_err(|_| symbol_fault("arcane_weft_entry"))?;
+            Ok(*Box::from_raw(entry() as *mut Box<dyn ArcaneWeft>))
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the library file was rebuilt since it was loaded
+    pub fn has_changed(&self) -> bool {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        modified.is_some() && modified != self.modified
+    }
+
+    pub(crate) fn image(&self) -> &LibraryImage {
+        &self.image
+    }
+}
+
+impl Drop for ArcaneWeftLibrary {
+    fn drop(&mut self) {
+        // the plugin's code lives in the library, drop it before closing
+        self.arcane_weft.take();
+        self.library.take();
+        let _ = std::fs::remove_file(&self.shadow_path);
+    }
+}
+
+#[cfg(test)]
+impl LibraryImage {
+    pub(crate) fn from_ranges(ranges: Vec<(usize, usize)>) -> LibraryImage {
+        LibraryImage { ranges }
+    }
+}
+
+#[cfg(test)]
+impl ArcaneWeftLibrary {
+    /// A library record with no loaded code behind it
+    pub(crate) fn detached(
+        path: &Path,
+        image: LibraryImage,
+        forged_objects: Vec<uuid::Uuid>,
+    ) -> ArcaneWeftLibrary {
+        ArcaneWeftLibrary {
+            path: path.to_path_buf(),
+            shadow_path: path.to_path_buf(),
+            modified: None,
+            image,
+            forged_objects,
+            arcane_weft: None,
+            library: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vtable_of<T: ?Sized>(ptr: *const T) -> usize {
+        unsafe { std::mem::transmute_copy::<*const T, (usize, usize)>(&ptr).1 }
+    }
+
+    fn shadow_copies(file_name: &str) -> usize {
+        let prefix = format!("arcane-weft-{}-", std::process::id());
+        std::fs::read_dir(std::env::temp_dir())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with(&prefix) && name.ends_with(file_name))
+            .count()
+    }
+
+    #[test]
+    fn missing_library_is_a_load_fault() {
+        let result = ArcaneWeftLibrary::open(Path::new("/nonexistent/libnothing.so"));
+        assert!(matches!(
+            result,
+            Err(LostLostLandsFaultArcaneWeft::LibraryLoad(..))
+        ));
+    }
+
+    #[test]
+    fn broken_library_is_a_load_fault_and_leaves_no_shadow_copy() {
+        let file_name = format!("libbroken-{}.so", std::process::id());
+        let path = std::env::temp_dir().join(&file_name);
+        std::fs::write(&path, b"not a shared object").unwrap();
+
+        let result = ArcaneWeftLibrary::open(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert!(matches!(
+            result,
+            Err(LostLostLandsFaultArcaneWeft::LibraryLoad(..))
+        ));
+        assert_eq!(shadow_copies(&file_name), 0);
+    }
+
+    #[test]
+    fn image_owns_only_vtables_inside_its_ranges() {
+        let first: Box<dyn std::fmt::Debug> = Box::new(1u8);
+        let second: Box<dyn std::fmt::Debug> = Box::new("two");
+        let vtable = vtable_of(&*first);
+        let image = LibraryImage::from_ranges(vec![(vtable, vtable + 1)]);
+
+        assert!(image.owns(&*first));
+        assert!(!image.owns(&*second));
+        // thin pointers carry no vtable
+        assert!(!image.owns(&1u8 as *const u8));
+    }
+}
@@ -1,51 +1,965 @@
-use std::collections::HashMap;
+use std::{
+    cell::{Cell, Ref, RefCell},
+    collections::HashMap,
+    marker::PhantomData,
+};
 
-use super::EtherealFlow;
+use super::{
+    lost_lands_fault::LostLostLandsFaultDestinyRift,
+    lost_realm::LostRealm,
+    omen::{foretell, OmenCategory, OmenLevel},
+    EtherealFlow,
+};
 
-pub trait DestinyRift: EtherealFlow {}
+pub trait DestinyRift: EtherealFlow {
+    /// Concrete name of the event
+    fn rift_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// Turns a boxed rift back into its concrete type, handing the box back when it is another type
+pub fn downcast_rift<T: 'static + DestinyRift>(
+    event: Box<dyn DestinyRift>,
+) -> Result<Box<T>, Box<dyn DestinyRift>> {
+    if event.as_any().is::<T>() {
+        // the concrete type was just checked, so the data pointer is a valid `T`
+        Ok(unsafe { Box::from_raw(Box::into_raw(event) as *mut T) })
+    } else {
+        Err(event)
+    }
+}
+
+struct RiftEntry {
+    sequence: usize,
+    // flushes the event went through
+    age: u32,
+    // read by a reader, peeked or observed
+    seen: Cell<bool>,
+    event: Box<dyn DestinyRift>,
+}
+
+/// How long the events of one type stay queued, see `LostRealm::set_destiny_rift_retention`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RiftRetention {
+    /// Kept until they are taken or consumed
+    UntilConsumed,
+    /// Dropped on the nth flush after they were added
+    Frames(u32),
+    /// Only the newest event is kept
+    Latest,
+    /// At most this many events are kept, the oldest is dropped first
+    Ring(usize),
+}
+
+impl Default for RiftRetention {
+    // readers get one update to see events added after they ran
+    fn default() -> Self {
+        RiftRetention::Frames(2)
+    }
+}
+
+impl std::fmt::Display for RiftRetention {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RiftRetention::UntilConsumed => write!(f, "until consumed"),
+            RiftRetention::Frames(frames) => write!(f, "{} frames", frames),
+            RiftRetention::Latest => write!(f, "latest only"),
+            RiftRetention::Ring(capacity) => write!(f, "ring of {}", capacity),
+        }
+    }
+}
+
+/// Events of one type from the oldest to the newest, kept as long as their `RiftRetention` allows
+/// so every reader gets a chance to see them
+pub struct RiftQueue {
+    entries: Vec<RiftEntry>,
+    next_sequence: usize,
+    retention: RiftRetention,
+}
+
+impl RiftQueue {
+    fn new() -> RiftQueue {
+        RiftQueue {
+            entries: Vec::new(),
+            next_sequence: 0,
+            retention: RiftRetention::default(),
+        }
+    }
+
+    // returns the events the retention pushed out
+    fn push(&mut self, event: Box<dyn DestinyRift>) -> Vec<RiftEntry> {
+        self.entries.push(RiftEntry {
+            sequence: self.next_sequence,
+            age: 0,
+            seen: Cell::new(false),
+            event,
+        });
+        self.next_sequence += 1;
+        self.bound()
+    }
+
+    pub fn get_retention(&self) -> RiftRetention {
+        self.retention
+    }
+
+    fn set_retention(&mut self, retention: RiftRetention) -> Vec<RiftEntry> {
+        self.retention = retention;
+        self.bound()
+    }
+
+    // drops the oldest events past the capacity of the retention
+    fn bound(&mut self) -> Vec<RiftEntry> {
+        let capacity = match self.retention {
+            RiftRetention::Latest => 1,
+            RiftRetention::Ring(capacity) => capacity,
+            RiftRetention::UntilConsumed | RiftRetention::Frames(_) => return Vec::new(),
+        };
+        let excess = self.entries.len().saturating_sub(capacity);
+        self.entries.drain(..excess).collect()
+    }
+
+    fn age(&mut self) {
+        for entry in self.entries.iter_mut() {
+            entry.age += 1;
+        }
+    }
+
+    // drops the events that went through as many flushes as the retention allows
+    fn expire(&mut self) -> Vec<RiftEntry> {
+        let RiftRetention::Frames(frames) = self.retention else {
+            return Vec::new();
+        };
+        // entries are ordered by age, the oldest first
+        let expired = self
+            .entries
+            .iter()
+            .take_while(|entry| entry.age >= frames.max(1))
+            .count();
+        self.entries.drain(..expired).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Events from the oldest to the newest
+    pub fn iter(&self) -> impl Iterator<Item = &dyn DestinyRift> {
+        self.entries.iter().map(|entry| entry.event.as_ref())
+    }
+
+    // events a reader whose cursor is at `sequence` has not seen yet
+    fn read_from(&self, sequence: usize) -> impl Iterator<Item = &RiftEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.sequence >= sequence)
+    }
+
+    // events added before the last flush and after the one before
+    fn take_last_flushed(&mut self) -> Vec<RiftEntry> {
+        let start = self
+            .entries
+            .iter()
+            .position(|entry| entry.age == 1)
+            .unwrap_or(self.entries.len());
+        let end = start
+            + self.entries[start..]
+                .iter()
+                .take_while(|entry| entry.age == 1)
+                .count();
+        self.entries.drain(start..end).collect()
+    }
+
+    // puts back events taken by `take_last_flushed`, ahead of the ones added since
+    fn restore_last_flushed(&mut self, entries: Vec<RiftEntry>) -> Vec<RiftEntry> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.age == 0)
+            .unwrap_or(self.entries.len());
+        self.entries.splice(index..index, entries);
+        self.bound()
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<Box<dyn DestinyRift>> {
+        self.entries.pop().map(|entry| entry.event)
+    }
+
+    pub(crate) fn remove_first(
+        &mut self,
+        mut predicate: impl FnMut(&dyn DestinyRift) -> bool,
+    ) -> Option<Box<dyn DestinyRift>> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| predicate(entry.event.as_ref()))?;
+        Some(self.entries.remove(index).event)
+    }
+
+    // every event from the oldest to the newest, readers keep their cursors
+    fn drain(&mut self) -> impl Iterator<Item = Box<dyn DestinyRift>> + use<> {
+        std::mem::take(&mut self.entries)
+            .into_iter()
+            .map(|entry| entry.event)
+    }
+
+    #[cfg(feature = "hot_reload")]
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(&dyn DestinyRift) -> bool) {
+        self.entries.retain(|entry| keep(entry.event.as_ref()));
+    }
+}
+
+/// Events sent to one forged object, kept until taken or the object is destroyed
+#[derive(Default)]
+pub struct RiftInbox {
+    events: Vec<Box<dyn DestinyRift>>,
+}
+
+impl RiftInbox {
+    pub(crate) fn push(&mut self, event: Box<dyn DestinyRift>) {
+        self.events.push(event);
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn DestinyRift> {
+        self.events.iter().map(|event| event.as_ref())
+    }
+
+    pub(crate) fn take<T: 'static + DestinyRift>(&mut self) -> Option<T> {
+        let index = self
+            .events
+            .iter()
+            .position(|event| event.as_any().is::<T>())?;
+        downcast_rift::<T>(self.events.remove(index))
+            .ok()
+            .map(|event| *event)
+    }
+
+    pub(crate) fn drain<T: 'static + DestinyRift>(&mut self) -> Vec<T> {
+        let mut taken = Vec::new();
+        for event in std::mem::take(&mut self.events) {
+            match downcast_rift::<T>(event) {
+                Ok(event) => taken.push(*event),
+                Err(event) => self.events.push(event),
+            }
+        }
+        taken
+    }
+
+    pub(crate) fn peek<T: 'static + DestinyRift>(&self) -> Option<&T> {
+        self.events
+            .iter()
+            .find_map(|event| event.as_any().downcast_ref::<T>())
+    }
+
+    pub(crate) fn count<T: 'static + DestinyRift>(&self) -> usize {
+        self.events
+            .iter()
+            .filter(|event| event.as_any().is::<T>())
+            .count()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    #[cfg(feature = "hot_reload")]
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(&dyn DestinyRift) -> bool) {
+        self.events.retain(|event| keep(event.as_ref()));
+    }
+}
+
+// events sent from other threads
+pub(crate) type SentRift = Box<dyn DestinyRift + Send>;
+
+/// Queues destiny rifts from any thread, the realm adds them to its queues at the start of each `update`.
+/// Get one with `LostRealm::get_realm_sender`.
+#[derive(Clone)]
+pub struct RealmSender {
+    sender: std::sync::mpsc::Sender<SentRift>,
+}
+
+impl RealmSender {
+    pub(crate) fn new(sender: std::sync::mpsc::Sender<SentRift>) -> RealmSender {
+        RealmSender { sender }
+    }
+
+    /// Fails once the realm is dropped
+    pub fn send(
+        &self,
+        event: impl DestinyRift + Send + 'static,
+    ) -> Result<(), LostLostLandsFaultDestinyRift> {
+        self.sender.send(Box::new(event)).map_err(|error| {
+            LostLostLandsFaultDestinyRift::RealmClosed(error.0.rift_name().to_string())
+        })
+    }
+}
+
+/// Where a bubbling destiny rift is on its way
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RiftPhase {
+    /// Going down from the root to the parent of the target
+    Capture,
+    Target,
+    /// Going up from the parent of the target to the root
+    Bubble,
+}
+
+/// A destiny rift travelling through the transform hierarchy, see `LostRealm::bubble_destiny_rift_event`
+pub struct RiftPropagation {
+    event: Box<dyn DestinyRift>,
+    phase: RiftPhase,
+    target: uuid::Uuid,
+    current: uuid::Uuid,
+    handled: bool,
+    stopped: bool,
+}
+
+impl RiftPropagation {
+    pub(crate) fn new(event: Box<dyn DestinyRift>, target: uuid::Uuid) -> RiftPropagation {
+        RiftPropagation {
+            event,
+            phase: RiftPhase::Target,
+            target,
+            current: target,
+            handled: false,
+            stopped: false,
+        }
+    }
+
+    pub(crate) fn enter(&mut self, phase: RiftPhase, current: uuid::Uuid) {
+        self.phase = phase;
+        self.current = current;
+    }
+
+    /// The event when it is a `T`
+    pub fn get_event<T: 'static + DestinyRift>(&self) -> Option<&T> {
+        self.event.as_any().downcast_ref::<T>()
+    }
+
+    pub fn get_rift(&self) -> &dyn DestinyRift {
+        self.event.as_ref()
+    }
+
+    pub fn into_rift(self) -> Box<dyn DestinyRift> {
+        self.event
+    }
+
+    pub fn get_phase(&self) -> RiftPhase {
+        self.phase
+    }
+
+    /// Id of the forged object the event was sent to
+    pub fn get_target(&self) -> uuid::Uuid {
+        self.target
+    }
+
+    /// Id of the forged object whose traits are handling the event
+    pub fn get_current(&self) -> uuid::Uuid {
+        self.current
+    }
+
+    /// Marks the event as handled, it keeps propagating unless stopped
+    pub fn handle(&mut self) {
+        self.handled = true;
+    }
+
+    pub fn is_handled(&self) -> bool {
+        self.handled
+    }
+
+    /// The other traits of the current forged object still get the event, no other object does
+    pub fn stop_propagation(&mut self) {
+        self.stopped = true;
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+}
+
+/// Returned when scheduling an event, to cancel it with `LostRealm::cancel_scheduled_destiny_rift_event`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ScheduledRiftHandle(u64);
+
+impl std::fmt::Display for ScheduledRiftHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+// what is left before a scheduled event is added
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum RiftDelay {
+    Seconds(f32),
+    Frames(u32),
+}
+
+struct ScheduledRift {
+    handle: ScheduledRiftHandle,
+    delay: RiftDelay,
+    event: Box<dyn DestinyRift>,
+}
+
+/// Returned by `LostRealm::observe` to stop observing with `LostRealm::unobserve`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RiftObserverHandle(u64);
+
+impl std::fmt::Display for RiftObserverHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+// returns false once the observer should be dropped
+pub(crate) type RiftObserver = Box<dyn FnMut(&dyn DestinyRift, &mut LostRealm) -> bool>;
+
+pub(crate) struct RiftObserverEntry {
+    handle: RiftObserverHandle,
+    type_id: std::any::TypeId,
+    // forged object the observer is dropped with
+    target: Option<uuid::Uuid>,
+    pub(crate) callback: RiftObserver,
+}
+
+/// Events and observers taken out of the manager while observers run,
+/// so observers can use the realm and emit follow-up events
+pub(crate) struct RiftDispatch {
+    pub(crate) observers: Vec<RiftObserverEntry>,
+    events: Vec<(std::any::TypeId, Vec<RiftEntry>)>,
+}
+
+impl RiftDispatch {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Calls the observers of each event, oldest first for each type,
+    /// returns the observers to drop
+    pub(crate) fn run(
+        &mut self,
+        lost_realm: &mut LostRealm,
+        destiny_rift_manager: &RefCell<DestinyRiftManager>,
+    ) -> Vec<RiftObserverHandle> {
+        let mut dropped = Vec::new();
+        for (type_id, events) in self.events.iter() {
+            for entry in events {
+                for observer in self.observers.iter_mut() {
+                    if observer.type_id != *type_id
+                        || dropped.contains(&observer.handle)
+                        || destiny_rift_manager.borrow().is_unobserved(observer.handle)
+                    {
+                        continue;
+                    }
+                    entry.seen.set(true);
+                    if !(observer.callback)(entry.event.as_ref(), lost_realm) {
+                        dropped.push(observer.handle);
+                    }
+                }
+            }
+        }
+        dropped
+    }
+}
+
+/// Independent cursor over the events of type `T`.
+/// Each reader sees every event once, as long as it reads before the event retention runs out
+/// (every two updates with the default `RiftRetention::Frames(2)`).
+pub struct EventReader<T> {
+    cursor: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static + DestinyRift> EventReader<T> {
+    pub fn new() -> EventReader<T> {
+        EventReader {
+            cursor: 0,
+            marker: PhantomData,
+        }
+    }
+
+    /// Events written since the last read, from the oldest to the newest
+    pub fn read<'a>(&mut self, lost_realm: &'a LostRealm) -> RiftRead<'a, T> {
+        lost_realm.read_destiny_rift_events(self)
+    }
+
+    /// How many events the next read returns
+    pub fn len(&self, lost_realm: &LostRealm) -> usize {
+        lost_realm.count_unread_destiny_rift_events(self)
+    }
+
+    pub fn is_empty(&self, lost_realm: &LostRealm) -> bool {
+        self.len(lost_realm) == 0
+    }
+
+    /// Skips every unread event
+    pub fn clear(&mut self, lost_realm: &LostRealm) {
+        lost_realm.read_destiny_rift_events(self);
+    }
+}
+
+/// Events returned by `EventReader::read`.
+/// The guard borrows the destiny rift manager, drop it before taking events,
+/// events added meanwhile are queued until the next access after it is dropped
+pub struct RiftRead<'a, T> {
+    manager: Ref<'a, DestinyRiftManager>,
+    from: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<'a, T: 'static + DestinyRift> RiftRead<'a, T> {
+    pub(crate) fn new(manager: Ref<'a, DestinyRiftManager>, from: usize) -> Self {
+        RiftRead {
+            manager,
+            from,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.manager
+            .events
+            .get(&std::any::TypeId::of::<T>())
+            .into_iter()
+            .flat_map(|queue| queue.read_from(self.from))
+            .filter_map(|entry| entry.event.as_any().downcast_ref::<T>())
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<'b, T: 'static + DestinyRift> IntoIterator for &'b RiftRead<'_, T> {
+    type Item = &'b T;
+    type IntoIter = Box<dyn Iterator<Item = &'b T> + 'b>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
+
+impl<T: 'static + DestinyRift> Default for EventReader<T> {
+    fn default() -> Self {
+        EventReader::new()
+    }
+}
 
 // alias for events
 pub struct DestinyRiftManager {
-    pub events: HashMap<std::any::TypeId, Vec<Box<dyn DestinyRift>>>,
+    pub events: HashMap<std::any::TypeId, RiftQueue>,
+    // consumed events stay alive until the next flush so references to them remain valid
+    pub(crate) consumed_events: Vec<Box<dyn DestinyRift>>,
+    pub(crate) observers: Vec<RiftObserverEntry>,
+    next_observer: u64,
+    // observers taken out by a dispatch, and the ones unobserved meanwhile
+    dispatching: Vec<(RiftObserverHandle, Option<uuid::Uuid>)>,
+    unobserved: Vec<RiftObserverHandle>,
+    scheduled: Vec<ScheduledRift>,
+    next_scheduled: u64,
 }
 
 impl DestinyRiftManager {
     pub fn new() -> DestinyRiftManager {
         DestinyRiftManager {
             events: HashMap::new(),
+            consumed_events: Vec::new(),
+            observers: Vec::new(),
+            next_observer: 0,
+            dispatching: Vec::new(),
+            unobserved: Vec::new(),
+            scheduled: Vec::new(),
+            next_scheduled: 0,
         }
     }
 
     pub fn add_event(&mut self, event: Box<dyn DestinyRift>) {
-        if let Some(ev) = self.events.get_mut(&event.as_any().type_id()) {
-            ev.push(event);
+        foretell(OmenLevel::Trace, OmenCategory::DestinyRift, || {
+            format!("Destiny rift {} added", event.rift_name())
+        });
+        let dropped = self
+            .events
+            .entry(event.as_any().type_id())
+            .or_insert_with(RiftQueue::new)
+            .push(event);
+        Self::report_dropped(dropped, "its retention is full");
+    }
+
+    pub fn set_retention(&mut self, type_id: std::any::TypeId, retention: RiftRetention) {
+        let dropped = self
+            .events
+            .entry(type_id)
+            .or_insert_with(RiftQueue::new)
+            .set_retention(retention);
+        Self::report_dropped(dropped, "its retention is full");
+    }
+
+    pub fn get_retention(&self, type_id: std::any::TypeId) -> RiftRetention {
+        self.events
+            .get(&type_id)
+            .map_or_else(RiftRetention::default, RiftQueue::get_retention)
+    }
+
+    // events dropped before anything saw them are worth a warning
+    fn report_dropped(dropped: Vec<RiftEntry>, reason: &str) {
+        let Some(first) = dropped.first() else {
             return;
+        };
+        let rift_name = first.event.rift_name();
+        let unseen = dropped.iter().filter(|entry| !entry.seen.get()).count();
+        if unseen > 0 {
+            foretell(OmenLevel::Warn, OmenCategory::DestinyRift, || {
+                format!(
+                    "Dropped {} pending destiny rifts {} that were never read, {}",
+                    unseen, rift_name, reason
+                )
+            });
+        }
+        if dropped.len() > unseen {
+            foretell(OmenLevel::Trace, OmenCategory::DestinyRift, || {
+                format!(
+                    "Dropped {} destiny rifts {}, {}",
+                    dropped.len() - unseen,
+                    rift_name,
+                    reason
+                )
+            });
         }
-        self.events.insert(event.as_any().type_id(), vec![event]);
     }
 
+    /// Flushes the queues, events are dropped once their `RiftRetention` runs out
     pub fn remove_event(&mut self) {
-        let keys_to_remove = self
+        self.advance_events();
+        self.expire_events();
+    }
+
+    // first half of a flush, consumed events go away and queued events get older
+    pub(crate) fn advance_events(&mut self) {
+        self.consumed_events.clear();
+        // queues are kept even when empty so the cursors of readers stay valid
+        for queue in self.events.values_mut() {
+            queue.age();
+        }
+    }
+
+    pub(crate) fn expire_events(&mut self) {
+        for queue in self.events.values_mut() {
+            let retention = queue.get_retention();
+            Self::report_dropped(queue.expire(), &format!("they expired after {}", retention));
+        }
+    }
+
+    /// Takes the oldest event of type `T` out of the queue
+    pub fn take_event<T: 'static + DestinyRift>(&mut self) -> Option<T> {
+        let event = self
             .events
+            .get_mut(&std::any::TypeId::of::<T>())?
+            .remove_first(|_| true)?;
+        downcast_rift::<T>(event).ok().map(|event| *event)
+    }
+
+    /// Takes every event of type `T` out of the queue, from the oldest to the newest
+    pub fn drain_events<T: 'static + DestinyRift>(&mut self) -> impl Iterator<Item = T> + use<T> {
+        let events = self
+            .events
+            .get_mut(&std::any::TypeId::of::<T>())
+            .map(|queue| queue.drain().collect::<Vec<_>>())
+            .unwrap_or_default();
+        events
+            .into_iter()
+            .filter_map(|event| downcast_rift::<T>(event).ok())
+            .map(|event| *event)
+    }
+
+    /// The event `take_event` would return, left in the queue
+    pub fn peek_event<T: 'static + DestinyRift>(&self) -> Option<&T> {
+        self.events
+            .get(&std::any::TypeId::of::<T>())?
+            .read_from(0)
+            .next()
+            .and_then(|entry| {
+                entry.seen.set(true);
+                entry.event.as_any().downcast_ref::<T>()
+            })
+    }
+
+    pub fn count_events<T: 'static + DestinyRift>(&self) -> usize {
+        self.events
+            .get(&std::any::TypeId::of::<T>())
+            .map_or(0, RiftQueue::len)
+    }
+
+    pub fn has_events<T: 'static + DestinyRift>(&self) -> bool {
+        self.count_events::<T>() > 0
+    }
+
+    /// Takes the last event of type `T`, it can be read until the next flush.
+    /// Prefer `take_event`, which hands over the event itself.
+    pub fn consume_event<T: 'static + DestinyRift>(
+        &mut self,
+    ) -> Result<&T, LostLostLandsFaultDestinyRift> {
+        // remove last ev
+        let ev = self
+            .events
+            .get_mut(&std::any::TypeId::of::<T>())
+            .and_then(RiftQueue::pop)
+            .ok_or_else(|| {
+                LostLostLandsFaultDestinyRift::RiftNotFound(std::any::type_name::<T>().to_string())
+            })?;
+        self.consumed_events.push(ev);
+        self.consumed_events
+            .last()
+            .and_then(|ev| ev.as_any().downcast_ref::<T>())
+            .ok_or_else(|| {
+                LostLostLandsFaultDestinyRift::RiftNotFound(std::any::type_name::<T>().to_string())
+            })
+    }
+
+    // the event `consume_event` took last, alive until the next flush
+    pub(crate) fn get_last_consumed_event<T: 'static + DestinyRift>(&self) -> Option<&T> {
+        self.consumed_events
+            .last()
+            .and_then(|event| event.as_any().downcast_ref::<T>())
+    }
+
+    pub(crate) fn schedule_event(
+        &mut self,
+        event: Box<dyn DestinyRift>,
+        delay: RiftDelay,
+    ) -> ScheduledRiftHandle {
+        // NaN would never run out, it is due on the next update like a negative delay
+        let delay = match delay {
+            RiftDelay::Seconds(seconds) if seconds.is_nan() => RiftDelay::Seconds(0.0),
+            delay => delay,
+        };
+        let handle = ScheduledRiftHandle(self.next_scheduled);
+        self.next_scheduled += 1;
+        foretell(OmenLevel::Trace, OmenCategory::DestinyRift, || {
+            format!("Destiny rift {} scheduled as {}", event.rift_name(), handle)
+        });
+        self.scheduled.push(ScheduledRift {
+            handle,
+            delay,
+            event,
+        });
+        handle
+    }
+
+    pub(crate) fn cancel_scheduled_event(
+        &mut self,
+        handle: ScheduledRiftHandle,
+    ) -> Result<Box<dyn DestinyRift>, LostLostLandsFaultDestinyRift> {
+        let index = self
+            .scheduled
+            .iter()
+            .position(|scheduled| scheduled.handle == handle)
+            .ok_or_else(|| {
+                LostLostLandsFaultDestinyRift::ScheduledRiftNotFound(handle.to_string())
+            })?;
+        Ok(self.scheduled.remove(index).event)
+    }
+
+    pub fn is_event_scheduled(&self, handle: ScheduledRiftHandle) -> bool {
+        self.scheduled
             .iter()
-            .filter(|(_, ev)| ev.is_empty())
-            .map(|(key, _)| *key)
+            .any(|scheduled| scheduled.handle == handle)
+    }
+
+    pub fn count_scheduled_events(&self) -> usize {
+        self.scheduled.len()
+    }
+
+    // advances every scheduled event by one frame of `delta_time`, returns the ones that are due
+    pub(crate) fn tick_scheduled_events(&mut self, delta_time: f32) -> Vec<Box<dyn DestinyRift>> {
+        let mut due = Vec::new();
+        for mut scheduled in std::mem::take(&mut self.scheduled) {
+            scheduled.delay = match scheduled.delay {
+                RiftDelay::Seconds(seconds) => RiftDelay::Seconds(seconds - delta_time),
+                RiftDelay::Frames(frames) => RiftDelay::Frames(frames.saturating_sub(1)),
+            };
+            match scheduled.delay {
+                RiftDelay::Seconds(seconds) if seconds <= 0.0 => due.push(scheduled.event),
+                RiftDelay::Frames(0) => due.push(scheduled.event),
+                _ => self.scheduled.push(scheduled),
+            }
+        }
+        due
+    }
+
+    #[cfg(feature = "hot_reload")]
+    pub(crate) fn retain_scheduled_events(
+        &mut self,
+        mut keep: impl FnMut(&dyn DestinyRift) -> bool,
+    ) {
+        self.scheduled
+            .retain(|scheduled| keep(scheduled.event.as_ref()));
+    }
+
+    pub(crate) fn add_observer(
+        &mut self,
+        type_id: std::any::TypeId,
+        target: Option<uuid::Uuid>,
+        callback: RiftObserver,
+    ) -> RiftObserverHandle {
+        let handle = RiftObserverHandle(self.next_observer);
+        self.next_observer += 1;
+        self.observers.push(RiftObserverEntry {
+            handle,
+            type_id,
+            target,
+            callback,
+        });
+        handle
+    }
+
+    pub(crate) fn remove_observer(
+        &mut self,
+        handle: RiftObserverHandle,
+    ) -> Result<(), LostLostLandsFaultDestinyRift> {
+        if let Some(index) = self
+            .observers
+            .iter()
+            .position(|observer| observer.handle == handle)
+        {
+            self.observers.remove(index);
+            return Ok(());
+        }
+        let dispatching = self
+            .dispatching
+            .iter()
+            .any(|(dispatching, _)| *dispatching == handle);
+        if dispatching && !self.unobserved.contains(&handle) {
+            self.unobserved.push(handle);
+            return Ok(());
+        }
+        Err(LostLostLandsFaultDestinyRift::ObserverNotFound(
+            handle.to_string(),
+        ))
+    }
+
+    // drops the observers of a destroyed forged object, the dispatching ones once the dispatch ends
+    pub(crate) fn remove_observers_of(&mut self, target: uuid::Uuid) -> Vec<RiftObserverHandle> {
+        let mut removed = Vec::new();
+        self.observers.retain(|observer| {
+            let owned = observer.target == Some(target);
+            if owned {
+                removed.push(observer.handle);
+            }
+            !owned
+        });
+        for (handle, observer_target) in self.dispatching.iter() {
+            if *observer_target == Some(target) && !self.unobserved.contains(handle) {
+                self.unobserved.push(*handle);
+                removed.push(*handle);
+            }
+        }
+        removed
+    }
+
+    pub(crate) fn is_unobserved(&self, handle: RiftObserverHandle) -> bool {
+        self.unobserved.contains(&handle)
+    }
+
+    // takes out the events of the last update that have observers, right after `advance_events`
+    pub(crate) fn begin_dispatch(&mut self) -> RiftDispatch {
+        let observers = std::mem::take(&mut self.observers);
+        let mut events = Vec::new();
+        for (type_id, queue) in self.events.iter_mut() {
+            let observed = observers
+                .iter()
+                .any(|observer| observer.type_id == *type_id);
+            if observed {
+                let last_flushed = queue.take_last_flushed();
+                if !last_flushed.is_empty() {
+                    events.push((*type_id, last_flushed));
+                }
+            }
+        }
+        self.dispatching = observers
+            .iter()
+            .map(|observer| (observer.handle, observer.target))
+            .collect();
+        RiftDispatch { observers, events }
+    }
+
+    // puts the events back and keeps the observers that are still wanted
+    pub(crate) fn end_dispatch(&mut self, dispatch: RiftDispatch, dropped: &[RiftObserverHandle]) {
+        let unobserved = std::mem::take(&mut self.unobserved);
+        let mut observers = dispatch
+            .observers
+            .into_iter()
+            .filter(|observer| {
+                !unobserved.contains(&observer.handle) && !dropped.contains(&observer.handle)
+            })
             .collect::<Vec<_>>();
+        // observers added while dispatching come after the existing ones
+        observers.append(&mut self.observers);
+        self.observers = observers;
+        self.dispatching.clear();
 
-        for key in keys_to_remove {
-            println!("Removing event: {:?}", key);
-            self.events.remove(&key);
+        for (type_id, events) in dispatch.events {
+            let queue = self.events.entry(type_id).or_insert_with(RiftQueue::new);
+            Self::report_dropped(queue.restore_last_flushed(events), "its retention is full");
         }
     }
 
-    pub fn consume_event<T: 'static + DestinyRift>(&mut self) -> Option<&T> {
-        // remove last ev
-        if let Some(ev) = self.events.get_mut(&std::any::TypeId::of::<T>()) {
-            let ev = ev.pop().unwrap();
-            let ev = ev.as_any().downcast_ref::<T>().unwrap();
-            return Some(unsafe { std::mem::transmute::<&T, &'static T>(ev) });
+    /// Events of type `T` past the reader's cursor, moving the cursor to the end
+    pub fn read_events<T: 'static + DestinyRift>(&self, reader: &mut EventReader<T>) -> Vec<&T> {
+        let from = self.advance_reader(reader);
+        self.events
+            .get(&std::any::TypeId::of::<T>())
+            .into_iter()
+            .flat_map(|queue| queue.read_from(from))
+            .filter_map(|entry| entry.event.as_any().downcast_ref::<T>())
+            .collect()
+    }
+
+    // marks the unread events as seen and moves the reader past them, returning where it was
+    pub(crate) fn advance_reader<T: 'static + DestinyRift>(
+        &self,
+        reader: &mut EventReader<T>,
+    ) -> usize {
+        let from = reader.cursor;
+        if let Some(queue) = self.events.get(&std::any::TypeId::of::<T>()) {
+            queue.read_from(from).for_each(|entry| entry.seen.set(true));
+            reader.cursor = queue.next_sequence;
         }
-        None
+        from
+    }
+
+    // sequence the next event of the type will get, later events have higher ones
+    pub(crate) fn get_next_sequence(&self, type_id: std::any::TypeId) -> usize {
+        self.events
+            .get(&type_id)
+            .map_or(0, |queue| queue.next_sequence)
+    }
+
+    // a reader that only sees events added from now on
+    pub(crate) fn reader_at_end<T: 'static + DestinyRift>(&self) -> EventReader<T> {
+        let mut reader = EventReader::new();
+        reader.cursor = self.get_next_sequence(std::any::TypeId::of::<T>());
+        reader
+    }
+
+    pub fn count_unread_events<T: 'static + DestinyRift>(&self, reader: &EventReader<T>) -> usize {
+        self.events
+            .get(&std::any::TypeId::of::<T>())
+            .map_or(0, |queue| queue.read_from(reader.cursor).count())
     }
 }
 
@@ -54,3 +968,183 @@ impl Default for DestinyRiftManager {
         DestinyRiftManager::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        omen::{add_omen_sink, remove_omen_sink, CaptureOmenSink},
+        testing::test_rift,
+    };
+
+    struct Spark;
+    struct Ping(u32);
+    test_rift!(Spark, Ping);
+
+    fn read(reader: &mut EventReader<Ping>, lost_realm: &LostRealm) -> Vec<u32> {
+        reader.read(lost_realm).iter().map(|ping| ping.0).collect()
+    }
+
+    #[test]
+    fn readers_see_every_event_once() {
+        let lost_realm = LostRealm::new();
+        let mut first = EventReader::<Ping>::new();
+        let mut second = EventReader::<Ping>::new();
+        lost_realm.add_destiny_rift_event(Ping(1));
+        assert_eq!(read(&mut first, &lost_realm), vec![1]);
+        lost_realm.add_destiny_rift_event(Ping(2));
+        assert_eq!(read(&mut first, &lost_realm), vec![2]);
+        assert_eq!(read(&mut second, &lost_realm), vec![1, 2]);
+        assert!(first.is_empty(&lost_realm));
+    }
+
+    #[test]
+    fn read_events_stay_valid_after_the_guard_is_dropped() {
+        let lost_realm = LostRealm::new();
+        let mut reader = EventReader::<Ping>::new();
+        lost_realm.add_destiny_rift_event(Ping(1));
+        assert_eq!(read(&mut reader, &lost_realm), vec![1]);
+        assert_eq!(lost_realm.drain_destiny_rift_events::<Ping>().count(), 1);
+        assert!(read(&mut reader, &lost_realm).is_empty());
+    }
+
+    #[test]
+    fn events_added_while_reading_wait_for_the_guard() {
+        let lost_realm = LostRealm::new();
+        let mut reader = EventReader::<Ping>::new();
+        lost_realm.add_destiny_rift_event(Ping(1));
+        {
+            let events = reader.read(&lost_realm);
+            for event in &events {
+                lost_realm.add_destiny_rift_event(Ping(event.0 + 1));
+            }
+            let peeked = lost_realm.peek_destiny_rift_event::<Ping>().unwrap();
+            lost_realm.add_destiny_rift_event(Ping(peeked.0 + 2));
+            assert_eq!(events.len(), 1);
+        }
+        assert_eq!(read(&mut reader, &lost_realm), vec![2, 3]);
+        assert_eq!(lost_realm.count_destiny_rift_events::<Ping>(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "already borrowed")]
+    fn drain_while_reading_panics_instead_of_freeing_the_events() {
+        let lost_realm = LostRealm::new();
+        let mut reader = EventReader::<Ping>::new();
+        lost_realm.add_destiny_rift_event(Ping(1));
+        let events = reader.read(&lost_realm);
+        let _ = lost_realm.drain_destiny_rift_events::<Ping>().count();
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn nan_and_negative_delays_are_due_on_the_next_update() {
+        let mut destiny_rift_manager = DestinyRiftManager::new();
+        destiny_rift_manager.schedule_event(Box::new(Spark), RiftDelay::Seconds(f32::NAN));
+        destiny_rift_manager.schedule_event(Box::new(Spark), RiftDelay::Seconds(-1.0));
+        assert_eq!(destiny_rift_manager.tick_scheduled_events(0.0).len(), 2);
+        assert_eq!(destiny_rift_manager.count_scheduled_events(), 0);
+    }
+
+    #[test]
+    fn scheduled_events_are_due_once_their_delay_ran_out() {
+        let mut destiny_rift_manager = DestinyRiftManager::new();
+        destiny_rift_manager.schedule_event(Box::new(Spark), RiftDelay::Seconds(1.0));
+        destiny_rift_manager.schedule_event(Box::new(Spark), RiftDelay::Frames(2));
+        destiny_rift_manager.schedule_event(Box::new(Spark), RiftDelay::Frames(0));
+        assert_eq!(destiny_rift_manager.tick_scheduled_events(0.5).len(), 1);
+        assert_eq!(destiny_rift_manager.count_scheduled_events(), 2);
+        assert_eq!(destiny_rift_manager.tick_scheduled_events(0.5).len(), 2);
+        assert_eq!(destiny_rift_manager.count_scheduled_events(), 0);
+    }
+
+    #[test]
+    fn cancelled_events_are_never_due() {
+        let mut destiny_rift_manager = DestinyRiftManager::new();
+        let handle = destiny_rift_manager.schedule_event(Box::new(Spark), RiftDelay::Frames(1));
+        assert!(destiny_rift_manager.is_event_scheduled(handle));
+        assert!(destiny_rift_manager.cancel_scheduled_event(handle).is_ok());
+        assert!(!destiny_rift_manager.is_event_scheduled(handle));
+        assert!(destiny_rift_manager.tick_scheduled_events(1.0).is_empty());
+        assert!(matches!(
+            destiny_rift_manager.cancel_scheduled_event(handle),
+            Err(LostLostLandsFaultDestinyRift::ScheduledRiftNotFound(..))
+        ));
+    }
+
+    fn manager_with(
+        retention: RiftRetention,
+        pings: impl IntoIterator<Item = u32>,
+    ) -> DestinyRiftManager {
+        let mut manager = DestinyRiftManager::new();
+        manager.set_retention(std::any::TypeId::of::<Ping>(), retention);
+        for ping in pings {
+            manager.add_event(Box::new(Ping(ping)));
+        }
+        manager
+    }
+
+    fn pings(manager: &mut DestinyRiftManager) -> Vec<u32> {
+        manager.drain_events::<Ping>().map(|ping| ping.0).collect()
+    }
+
+    #[test]
+    fn frames_retention_expires_on_the_nth_flush() {
+        let mut manager = manager_with(RiftRetention::Frames(2), [1]);
+        manager.remove_event();
+        assert_eq!(manager.count_events::<Ping>(), 1);
+        manager.remove_event();
+        assert_eq!(manager.count_events::<Ping>(), 0);
+    }
+
+    #[test]
+    fn until_consumed_retention_never_expires() {
+        let mut manager = manager_with(RiftRetention::UntilConsumed, [1, 2]);
+        for _ in 0..10 {
+            manager.remove_event();
+        }
+        assert_eq!(pings(&mut manager), vec![1, 2]);
+    }
+
+    #[test]
+    fn latest_retention_keeps_the_newest_event() {
+        let mut manager = manager_with(RiftRetention::Latest, [1, 2, 3]);
+        assert_eq!(pings(&mut manager), vec![3]);
+    }
+
+    #[test]
+    fn ring_retention_drops_the_oldest_events() {
+        let mut manager = manager_with(RiftRetention::Ring(2), [1, 2, 3]);
+        assert_eq!(pings(&mut manager), vec![2, 3]);
+    }
+
+    #[test]
+    fn shrinking_the_retention_drops_queued_events() {
+        let mut manager = manager_with(RiftRetention::UntilConsumed, [1, 2, 3]);
+        manager.set_retention(std::any::TypeId::of::<Ping>(), RiftRetention::Ring(1));
+        assert_eq!(pings(&mut manager), vec![3]);
+    }
+
+    #[test]
+    fn only_unread_expired_events_are_warned_about() {
+        let sink = CaptureOmenSink::new();
+        let sink_id = add_omen_sink(sink.clone());
+        let mut lost_realm = LostRealm::new();
+        lost_realm.set_destiny_rift_retention::<Ping>(RiftRetention::Frames(1));
+        let mut reader = EventReader::<Ping>::new();
+        lost_realm.add_destiny_rift_event(Ping(1));
+        assert_eq!(read(&mut reader, &lost_realm), vec![1]);
+        lost_realm.add_destiny_rift_event(Ping(2));
+        lost_realm.update();
+        remove_omen_sink(sink_id);
+        let warnings = sink
+            .get_omens()
+            .into_iter()
+            .filter(|omen| omen.level == OmenLevel::Warn)
+            .map(|omen| omen.message)
+            .collect::<Vec<_>>();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Dropped 1 pending destiny rifts"));
+        assert_eq!(lost_realm.count_destiny_rift_events::<Ping>(), 0);
+    }
+}
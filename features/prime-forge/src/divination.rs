@@ -0,0 +1,452 @@
+use std::{any::TypeId, cell::RefCell, collections::HashMap, marker::PhantomData};
+
+use crate::{forged_object::ForgedObject, forged_trait::ForgedTrait};
+
+/// Traits fetched from each forged object matched by a divination.
+/// `&T` and `&mut T` require the trait, `Option<&T>` and `Option<&mut T>` fetch it when present.
+pub trait DivinationFetch {
+    type Item<'a>;
+
+    fn access(accesses: &mut Vec<DivinationAccess>);
+
+    fn fetch(forged_object: &ForgedObject) -> Option<Self::Item<'_>>;
+}
+
+/// A trait type read or written by a divination
+#[derive(Debug, Clone, Copy)]
+pub struct DivinationAccess {
+    type_id: TypeId,
+    name: &'static str,
+    mutable: bool,
+}
+
+impl DivinationAccess {
+    pub fn of<T: 'static>(mutable: bool) -> Self {
+        DivinationAccess {
+            type_id: TypeId::of::<T>(),
+            name: std::any::type_name::<T>(),
+            mutable,
+        }
+    }
+}
+
+/// Trait types held by the live divinations of a realm
+/// a type is either read by any number of divinations or written by one
+#[derive(Default)]
+pub(crate) struct DivinationClaims {
+    claims: HashMap<TypeId, isize>,
+    live: usize,
+}
+
+impl DivinationClaims {
+    pub(crate) fn is_divining(&self) -> bool {
+        self.live > 0
+    }
+
+    fn claim(&mut self, accesses: &[DivinationAccess]) {
+        for (index, access) in accesses.iter().enumerate() {
+            let twin = accesses[..index]
+                .iter()
+                .find(|other| other.type_id == access.type_id);
+            if let Some(twin) = twin {
+                if access.mutable || twin.mutable {
+                    panic!("Divination fetches {} more than once mutably", access.name);
+                }
+            }
+            match self.claims.get(&access.type_id) {
+                Some(-1) => panic!("{} is already divined mutably", access.name),
+                Some(_) if access.mutable => panic!("{} is already divined", access.name),
+                _ => {}
+            }
+        }
+        for access in accesses {
+            let claim = self.claims.entry(access.type_id).or_insert(0);
+            *claim = if access.mutable { -1 } else { *claim + 1 };
+        }
+        self.live += 1;
+    }
+
+    fn release(&mut self, accesses: &[DivinationAccess]) {
+        for access in accesses {
+            let Some(claim) = self.claims.get_mut(&access.type_id) else {
+                continue;
+            };
+            if access.mutable || *claim <= 1 {
+                self.claims.remove(&access.type_id);
+            } else {
+                *claim -= 1;
+            }
+        }
+        self.live -= 1;
+    }
+}
+
+struct DivinationClaim<'a> {
+    claims: &'a RefCell<DivinationClaims>,
+    accesses: Vec<DivinationAccess>,
+}
+
+impl Drop for DivinationClaim<'_> {
+    fn drop(&mut self) {
+        self.claims.borrow_mut().release(&self.accesses);
+    }
+}
+
+impl DivinationFetch for () {
+    type Item<'a> = ();
+
+    fn access(_accesses: &mut Vec<DivinationAccess>) {}
+
+    fn fetch(_forged_object: &ForgedObject) -> Option<Self::Item<'_>> {
+        Some(())
+    }
+}
+
+impl<T: ForgedTrait + 'static> DivinationFetch for &T {
+    type Item<'a> = &'a T;
+
+    fn access(accesses: &mut Vec<DivinationAccess>) {
+        accesses.push(DivinationAccess::of::<T>(false));
+    }
+
+    fn fetch(forged_object: &ForgedObject) -> Option<Self::Item<'_>> {
+        forged_object.get_trait::<T>().ok()
+    }
+}
+
+impl<T: ForgedTrait + 'static> DivinationFetch for &mut T {
+    type Item<'a> = &'a mut T;
+
+    fn access(accesses: &mut Vec<DivinationAccess>) {
+        accesses.push(DivinationAccess::of::<T>(true));
+    }
+
+    fn fetch(forged_object: &ForgedObject) -> Option<Self::Item<'_>> {
+        forged_object.get_trait_mut::<T>().ok()
+    }
+}
+
+impl<T: ForgedTrait + 'static> DivinationFetch for Option<&T> {
+    type Item<'a> = Option<&'a T>;
+
+    fn access(accesses: &mut Vec<DivinationAccess>) {
+        accesses.push(DivinationAccess::of::<T>(false));
+    }
+
+    fn fetch(forged_object: &ForgedObject) -> Option<Self::Item<'_>> {
+        Some(forged_object.get_trait::<T>().ok())
+    }
+}
+
+impl<T: ForgedTrait + 'static> DivinationFetch for Option<&mut T> {
+    type Item<'a> = Option<&'a mut T>;
+
+    fn access(accesses: &mut Vec<DivinationAccess>) {
+        accesses.push(DivinationAccess::of::<T>(true));
+    }
+
+    fn fetch(forged_object: &ForgedObject) -> Option<Self::Item<'_>> {
+        Some(forged_object.get_trait_mut::<T>().ok())
+    }
+}
+
+/// Decides whether a forged object takes part in a divination without fetching anything
+pub trait DivinationFilter {
+    fn matches(forged_object: &ForgedObject) -> bool;
+}
+
+pub struct With<T>(PhantomData<T>);
+
+pub struct Without<T>(PhantomData<T>);
+
+/// Matches when any of the filters in the tuple matches
+pub struct Or<F>(PhantomData<F>);
+
+impl<T: ForgedTrait + 'static> DivinationFilter for With<T> {
+    fn matches(forged_object: &ForgedObject) -> bool {
+        forged_object.has_trait::<T>()
+    }
+}
+
+impl<T: ForgedTrait + 'static> DivinationFilter for Without<T> {
+    fn matches(forged_object: &ForgedObject) -> bool {
+        !forged_object.has_trait::<T>()
+    }
+}
+
+impl DivinationFilter for () {
+    fn matches(_forged_object: &ForgedObject) -> bool {
+        true
+    }
+}
+
+macro_rules! impl_divination_tuple {
+    ($($name: ident),*) => {
+        impl<$($name: DivinationFetch),*> DivinationFetch for ($($name,)*) {
+            type Item<'a> = ($($name::Item<'a>,)*);
+
+            fn access(accesses: &mut Vec<DivinationAccess>) {
+                $($name::access(accesses);)*
+            }
+
+            fn fetch(forged_object: &ForgedObject) -> Option<Self::Item<'_>> {
+                Some(($($name::fetch(forged_object)?,)*))
+            }
+        }
+
+        impl<$($name: DivinationFilter),*> DivinationFilter for ($($name,)*) {
+            fn matches(forged_object: &ForgedObject) -> bool {
+                $($name::matches(forged_object))&&*
+            }
+        }
+
+        impl<$($name: DivinationFilter),*> DivinationFilter for Or<($($name,)*)> {
+            fn matches(forged_object: &ForgedObject) -> bool {
+                $($name::matches(forged_object))||*
+            }
+        }
+    };
+}
+impl_divination_tuple!(A);
+impl_divination_tuple!(A, B);
+impl_divination_tuple!(A, B, C);
+impl_divination_tuple!(A, B, C, D);
+impl_divination_tuple!(A, B, C, D, E);
+impl_divination_tuple!(A, B, C, D, E, F);
+impl_divination_tuple!(A, B, C, D, E, F, G);
+impl_divination_tuple!(A, B, C, D, E, F, G, H);
+
+// alias for queries
+/// Holds its trait types until dropped, fetching a type mutably next to any other fetch of it panics.
+/// Forged objects can not be forged or destroyed while a divination is live.
+pub struct Divination<'a, Q, F = ()> {
+    forged_objects: &'a RefCell<Vec<ForgedObject>>,
+    claim: DivinationClaim<'a>,
+    marker: PhantomData<(Q, F)>,
+}
+
+impl<'a, Q: DivinationFetch, F: DivinationFilter> Divination<'a, Q, F> {
+    pub(crate) fn new(
+        forged_objects: &'a RefCell<Vec<ForgedObject>>,
+        claims: &'a RefCell<DivinationClaims>,
+    ) -> Self {
+        let mut accesses = Vec::new();
+        Q::access(&mut accesses);
+        claims.borrow_mut().claim(&accesses);
+        Divination {
+            forged_objects,
+            claim: DivinationClaim { claims, accesses },
+            marker: PhantomData,
+        }
+    }
+
+    fn refine<G>(self) -> Divination<'a, Q, G> {
+        Divination {
+            forged_objects: self.forged_objects,
+            claim: self.claim,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn with<T: ForgedTrait + 'static>(self) -> Divination<'a, Q, (F, With<T>)> {
+        self.refine()
+    }
+
+    pub fn without<T: ForgedTrait + 'static>(self) -> Divination<'a, Q, (F, Without<T>)> {
+        self.refine()
+    }
+
+    /// Matches objects passing any of the filters, e.g. `or::<(With<A>, Without<B>)>()`
+    pub fn or<G>(self) -> Divination<'a, Q, (F, Or<G>)>
+    where
+        Or<G>: DivinationFilter,
+    {
+        self.refine()
+    }
+
+    pub fn filter<G: DivinationFilter>(self) -> Divination<'a, Q, (F, G)> {
+        self.refine()
+    }
+
+    /// Fetched traits live as long as the borrow of the divination, one iterator at a time
+    pub fn iter(&mut self) -> DivinationIter<'_, Q, F> {
+        DivinationIter {
+            forged_objects: self.forged_objects,
+            index: 0,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'d, Q: DivinationFetch, F: DivinationFilter> IntoIterator for &'d mut Divination<'_, Q, F> {
+    type Item = (&'d ForgedObject, Q::Item<'d>);
+    type IntoIter = DivinationIter<'d, Q, F>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct DivinationIter<'a, Q, F> {
+    forged_objects: &'a RefCell<Vec<ForgedObject>>,
+    index: usize,
+    marker: PhantomData<(Q, F)>,
+}
+
+impl<'a, Q: DivinationFetch, F: DivinationFilter> Iterator for DivinationIter<'a, Q, F> {
+    type Item = (&'a ForgedObject, Q::Item<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // read like the other accessors, `update` holds the objects while traits run
+        // the vector can not grow or shrink while the divination is live
+        let forged_objects = unsafe {
+            let ptr = self.forged_objects.as_ptr();
+            &*ptr
+        };
+        while let Some(forged_object) = forged_objects.get(self.index) {
+            self.index += 1;
+            if !F::matches(forged_object) {
+                continue;
+            }
+            if let Some(item) = Q::fetch(forged_object) {
+                return Some((forged_object, item));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lost_realm::LostRealm, testing::test_trait};
+
+    #[derive(Default)]
+    struct Ember {
+        father: Option<String>,
+        heat: u32,
+    }
+    test_trait!(Ember);
+    impl ForgedTrait for Ember {}
+
+    #[derive(Default)]
+    struct Ash {
+        father: Option<String>,
+    }
+    test_trait!(Ash);
+    impl ForgedTrait for Ash {}
+
+    // divines from its own update, while the lost realm holds it mutably borrowed
+    #[derive(Default)]
+    struct Bellows {
+        father: Option<String>,
+        seen: std::rc::Rc<std::cell::RefCell<Vec<usize>>>,
+    }
+    test_trait!(Bellows);
+    impl ForgedTrait for Bellows {
+        fn update(&mut self, lost_realm: &mut LostRealm, _dt: f32) {
+            for (_, ember) in &mut lost_realm.divine::<&mut Ember>() {
+                ember.heat += 1;
+            }
+            let itself = lost_realm.divine::<&mut Bellows>().iter().count();
+            let with = lost_realm
+                .divine::<&Ember>()
+                .with::<Bellows>()
+                .iter()
+                .count();
+            let without = lost_realm
+                .divine::<Option<&mut Bellows>>()
+                .without::<Bellows>()
+                .iter()
+                .count();
+            self.seen.borrow_mut().extend([itself, with, without]);
+        }
+    }
+
+    #[test]
+    fn divining_from_an_update_skips_the_busy_trait() {
+        let mut lost_realm = LostRealm::new();
+        let bellows = Bellows::default();
+        let seen = bellows.seen.clone();
+        lost_realm
+            .forge_new_object("Forge", (Ember::default(), bellows))
+            .unwrap();
+        lost_realm
+            .forge_new_object("Pyre", (Ember::default(), Ash::default()))
+            .unwrap();
+        lost_realm.update();
+        assert_eq!(*seen.borrow(), vec![0, 0, 2]);
+        let heat = lost_realm
+            .divine::<&Ember>()
+            .iter()
+            .map(|(_, ember)| ember.heat)
+            .collect::<Vec<_>>();
+        assert_eq!(heat, vec![1, 1]);
+    }
+
+    #[test]
+    fn mutable_fetches_and_filters() {
+        let lost_realm = LostRealm::new();
+        lost_realm
+            .forge_new_object("Hearth", (Ember::default(),))
+            .unwrap();
+        lost_realm
+            .forge_new_object("Pyre", (Ember::default(), Ash::default()))
+            .unwrap();
+        for (_, ember) in &mut lost_realm.divine::<&mut Ember>() {
+            ember.heat += 1;
+        }
+        let cold = lost_realm
+            .divine::<(&Ember, Option<&Ash>)>()
+            .without::<Ash>()
+            .iter()
+            .map(|(object, (ember, ash))| (object.name.clone(), ember.heat, ash.is_some()))
+            .collect::<Vec<_>>();
+        assert_eq!(cold, vec![("Hearth".to_string(), 1, false)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "more than once mutably")]
+    fn duplicate_mutable_fetch_panics() {
+        let lost_realm = LostRealm::new();
+        let _ = lost_realm.divine::<(&mut Ember, &Ember)>();
+    }
+
+    #[test]
+    #[should_panic(expected = "already divined mutably")]
+    fn two_live_mutable_divinations_panic() {
+        let lost_realm = LostRealm::new();
+        let _first = lost_realm.divine::<&mut Ember>();
+        let _second = lost_realm.divine::<&mut Ember>();
+    }
+
+    #[test]
+    fn reads_share_and_claims_end_with_the_divination() {
+        let lost_realm = LostRealm::new();
+        {
+            let _first = lost_realm.divine::<&Ember>();
+            let _second = lost_realm.divine::<(&Ember, &mut Ash)>();
+        }
+        let _mutable = lost_realm.divine::<&mut Ember>();
+    }
+
+    #[test]
+    fn forging_and_destroying_wait_for_the_divination() {
+        let lost_realm = LostRealm::new();
+        lost_realm
+            .forge_new_object("Hearth", (Ember::default(),))
+            .unwrap();
+        let mut divination = lost_realm.divine::<&Ember>();
+        for _ in divination.iter() {
+            assert!(lost_realm
+                .forge_new_object("Spark", (Ember::default(),))
+                .is_err());
+            assert!(lost_realm.destroy_forged_object("Hearth").is_err());
+        }
+        drop(divination);
+        assert!(lost_realm
+            .forge_new_object("Spark", (Ember::default(),))
+            .is_ok());
+        assert!(lost_realm.destroy_forged_object("Hearth").is_ok());
+    }
+}
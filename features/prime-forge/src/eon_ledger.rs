@@ -0,0 +1,277 @@
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    time::{Duration, Instant},
+};
+
+use crate::runic_essence::escape_json;
+
+const DEFAULT_MAX_SPANS: usize = 1_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LedgerCategory {
+    TraitStart,
+    TraitUpdate,
+    SoulThreadResume,
+    DestinyRiftFlush,
+    ArcaneWeftCraft,
+}
+
+impl LedgerCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LedgerCategory::TraitStart => "forged_trait.start",
+            LedgerCategory::TraitUpdate => "forged_trait.update",
+            LedgerCategory::SoulThreadResume => "soul_thread.resume",
+            LedgerCategory::DestinyRiftFlush => "destiny_rift.flush",
+            LedgerCategory::ArcaneWeftCraft => "arcane_weft.craft",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LedgerStats {
+    pub calls: u64,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl LedgerStats {
+    fn record(&mut self, duration: Duration) {
+        self.calls += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+    }
+
+    pub fn average(&self) -> Duration {
+        if self.calls == 0 {
+            return Duration::ZERO;
+        }
+        self.total.div_f64(self.calls as f64)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LedgerSpan {
+    pub category: LedgerCategory,
+    pub name: String,
+    pub object: Option<(uuid::Uuid, String)>,
+    pub start: Duration,
+    pub duration: Duration,
+}
+
+// alias for profiler
+pub struct EonLedger {
+    epoch: Instant,
+    spans: Vec<LedgerSpan>,
+    max_spans: usize,
+    trait_stats: HashMap<&'static str, LedgerStats>,
+    object_stats: HashMap<uuid::Uuid, (String, LedgerStats)>,
+    category_stats: HashMap<&'static str, LedgerStats>,
+}
+
+impl EonLedger {
+    pub fn new() -> EonLedger {
+        EonLedger {
+            epoch: Instant::now(),
+            spans: Vec::new(),
+            max_spans: DEFAULT_MAX_SPANS,
+            trait_stats: HashMap::new(),
+            object_stats: HashMap::new(),
+            category_stats: HashMap::new(),
+        }
+    }
+
+    /// Spans past this amount are only aggregated, not kept for the trace export
+    pub fn set_max_spans(&mut self, max_spans: usize) {
+        self.max_spans = max_spans;
+    }
+
+    pub fn record_trait(
+        &mut self,
+        category: LedgerCategory,
+        trait_name: &'static str,
+        object_id: uuid::Uuid,
+        object_name: &str,
+        started: Instant,
+    ) {
+        let duration = started.elapsed();
+        self.trait_stats
+            .entry(trait_name)
+            .or_default()
+            .record(duration);
+        self.object_stats
+            .entry(object_id)
+            .or_insert_with(|| (object_name.to_string(), LedgerStats::default()))
+            .1
+            .record(duration);
+        self.push_span(
+            category,
+            trait_name.to_string(),
+            Some((object_id, object_name.to_string())),
+            started,
+            duration,
+        );
+    }
+
+    pub fn record(&mut self, category: LedgerCategory, name: &str, started: Instant) {
+        let duration = started.elapsed();
+        self.push_span(category, name.to_string(), None, started, duration);
+    }
+
+    fn push_span(
+        &mut self,
+        category: LedgerCategory,
+        name: String,
+        object: Option<(uuid::Uuid, String)>,
+        started: Instant,
+        duration: Duration,
+    ) {
+        self.category_stats
+            .entry(category.as_str())
+            .or_default()
+            .record(duration);
+        if self.spans.len() >= self.max_spans {
+            return;
+        }
+        self.spans.push(LedgerSpan {
+            category,
+            name,
+            object,
+            start: started.saturating_duration_since(self.epoch),
+            duration,
+        });
+    }
+
+    pub fn spans(&self) -> &[LedgerSpan] {
+        &self.spans
+    }
+
+    pub fn trait_stats(&self, trait_name: &str) -> Option<LedgerStats> {
+        self.trait_stats.get(trait_name).copied()
+    }
+
+    pub fn object_stats(&self, object_id: uuid::Uuid) -> Option<LedgerStats> {
+        self.object_stats.get(&object_id).map(|(_, stats)| *stats)
+    }
+
+    pub fn category_stats(&self, category: LedgerCategory) -> Option<LedgerStats> {
+        self.category_stats.get(category.as_str()).copied()
+    }
+
+    /// Traits ordered by the total time spent in their start and update
+    pub fn top_slowest_traits(&self, n: usize) -> Vec<(&'static str, LedgerStats)> {
+        let mut traits = self
+            .trait_stats
+            .iter()
+            .map(|(name, stats)| (*name, *stats))
+            .collect::<Vec<_>>();
+        traits.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.total));
+        traits.truncate(n);
+        traits
+    }
+
+    /// Forged objects ordered by the total time spent in their traits
+    pub fn top_slowest_objects(&self, n: usize) -> Vec<(uuid::Uuid, &str, LedgerStats)> {
+        let mut objects = self
+            .object_stats
+            .iter()
+            .map(|(id, (name, stats))| (*id, name.as_str(), *stats))
+            .collect::<Vec<_>>();
+        objects.sort_by_key(|(_, _, stats)| std::cmp::Reverse(stats.total));
+        objects.truncate(n);
+        objects
+    }
+
+    pub fn clear(&mut self) {
+        self.spans.clear();
+        self.trait_stats.clear();
+        self.object_stats.clear();
+        self.category_stats.clear();
+    }
+
+    /// Chrome trace-event JSON, loadable in chrome://tracing or Perfetto
+    pub fn to_chrome_trace(&self) -> String {
+        let mut json = String::from("{\"traceEvents\":[");
+        for (index, span) in self.spans.iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            let _ = write!(
+                json,
+                "{{\"name\":\"{}\",\"cat\":\"{}\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":1,\"tid\":1",
+                escape_json(&span.name),
+                span.category.as_str(),
+                span.start.as_secs_f64() * 1_000_000.0,
+                span.duration.as_secs_f64() * 1_000_000.0,
+            );
+            if let Some((id, name)) = &span.object {
+                let _ = write!(
+                    json,
+                    ",\"args\":{{\"object\":\"{}\",\"object_id\":\"{}\"}}",
+                    escape_json(name),
+                    id
+                );
+            }
+            json.push('}');
+        }
+        json.push_str("],\"displayTimeUnit\":\"ms\"}");
+        json
+    }
+
+    pub fn export_chrome_trace(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_chrome_trace())
+    }
+}
+
+impl Default for EonLedger {
+    fn default() -> Self {
+        EonLedger::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ago(millis: u64) -> Instant {
+        Instant::now() - Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn traits_are_ranked_by_total_time() {
+        let mut eon_ledger = EonLedger::new();
+        let object = uuid::Uuid::new_v4();
+        eon_ledger.record_trait(LedgerCategory::TraitUpdate, "Quick", object, "Hero", ago(1));
+        eon_ledger.record_trait(LedgerCategory::TraitUpdate, "Slow", object, "Hero", ago(30));
+        eon_ledger.record_trait(LedgerCategory::TraitUpdate, "Quick", object, "Hero", ago(1));
+        let top = eon_ledger.top_slowest_traits(1);
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].0, "Slow");
+        assert_eq!(eon_ledger.trait_stats("Quick").unwrap().calls, 2);
+        assert_eq!(eon_ledger.object_stats(object).unwrap().calls, 3);
+    }
+
+    #[test]
+    fn spans_past_the_limit_are_only_aggregated() {
+        let mut eon_ledger = EonLedger::new();
+        eon_ledger.set_max_spans(2);
+        for _ in 0..5 {
+            eon_ledger.record(
+                LedgerCategory::DestinyRiftFlush,
+                "flush_destiny_rifts",
+                ago(0),
+            );
+        }
+        assert_eq!(eon_ledger.spans().len(), 2);
+        let stats = eon_ledger
+            .category_stats(LedgerCategory::DestinyRiftFlush)
+            .unwrap();
+        assert_eq!(stats.calls, 5);
+        eon_ledger.clear();
+        assert!(eon_ledger.spans().is_empty());
+        assert!(eon_ledger
+            .category_stats(LedgerCategory::DestinyRiftFlush)
+            .is_none());
+    }
+}
@@ -2,6 +2,8 @@ use std::time::Duration;
 
 pub struct EonForge {
     delta_time: f32,
+    unscaled_delta_time: f32,
+    time_scale: f32,
     time_elapsed_since_start: Duration,
     last_time: f32,
 }
@@ -10,6 +12,8 @@ impl EonForge {
     pub fn new() -> EonForge {
         EonForge {
             delta_time: 0.0,
+            unscaled_delta_time: 0.0,
+            time_scale: 1.0,
             time_elapsed_since_start: Duration::new(0, 0),
             last_time: 0.0,
         }
@@ -21,22 +25,56 @@ impl EonForge {
 
     pub fn update(&mut self, current_time: f32) {
         self.time_elapsed_since_start += Duration::from_secs(1);
-        self.delta_time = current_time - self.last_time;
+        self.unscaled_delta_time = current_time - self.last_time;
+        self.delta_time = self.unscaled_delta_time * self.time_scale;
         self.last_time = current_time;
     }
 
+    /// Delta time multiplied by the time scale
     pub fn get_delta_time(&self) -> f32 {
         self.delta_time
     }
 
+    pub fn get_unscaled_delta_time(&self) -> f32 {
+        self.unscaled_delta_time
+    }
+
+    /// 1.0 by default, 0.0 freezes everything driven by delta time, negative scales are clamped to 0.0
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = time_scale.max(0.0);
+        self.delta_time = self.unscaled_delta_time * self.time_scale;
+    }
+
+    pub fn get_time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
     pub fn get_time_elapsed_since_start(&self) -> Duration {
         self.time_elapsed_since_start
     }
 }
 
-
 impl Default for EonForge {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_scale_only_affects_the_scaled_delta_time() {
+        let mut eonforge = EonForge::new();
+        eonforge.set_time_scale(0.5);
+        eonforge.update(2.0);
+        assert_eq!(eonforge.get_unscaled_delta_time(), 2.0);
+        assert_eq!(eonforge.get_delta_time(), 1.0);
+
+        eonforge.set_time_scale(-1.0);
+        assert_eq!(eonforge.get_time_scale(), 0.0);
+        assert_eq!(eonforge.get_delta_time(), 0.0);
+        assert_eq!(eonforge.get_unscaled_delta_time(), 2.0);
+    }
+}
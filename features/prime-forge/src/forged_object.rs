@@ -1,14 +1,29 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::{Cell, Ref, RefCell},
+    rc::Rc,
+};
 
 use crate::{forged_trait::TransformSpecialTrait, lost_realm::LostRealm};
 
-use super::{forged_trait::ForgedTrait, lost_lands_fault::LostLostLandsFaultForgedObject};
+#[cfg(feature = "profiling")]
+use crate::eon_ledger::LedgerCategory;
+
+use super::{
+    destiny_rift::{DestinyRift, RiftInbox, RiftPhase, RiftPropagation},
+    forged_trait::ForgedTrait,
+    lost_lands_fault::{LostLostLandsFaultForgedObject, LostLostLandsFaultTransform},
+    omen::{foretell, OmenCategory, OmenLevel},
+};
 
 pub struct ForgedObject {
     pub name: String,
     pub id: uuid::Uuid,
     pub forged_traits: Vec<Box<RefCell<dyn ForgedTrait>>>,
     pub transform: Rc<RefCell<TransformSpecialTrait>>,
+    // events sent to this object, see `LostRealm::send_destiny_rift_event`
+    pub(crate) inbox: RefCell<RiftInbox>,
+    // inactive objects are not updated and their soul threads wait, shared with those soul threads
+    pub(crate) active: Rc<Cell<bool>>,
 }
 
 impl ForgedObject {
@@ -18,20 +33,104 @@ impl ForgedObject {
             id: uuid::Uuid::new_v4(),
             forged_traits: Vec::new(),
             transform: Rc::new(RefCell::new(TransformSpecialTrait::new())),
+            inbox: RefCell::new(RiftInbox::default()),
+            active: Rc::new(Cell::new(true)),
+        }
+    }
+
+    /// An inactive object skips its updates and the soul threads it owns are paused
+    pub fn set_active(&self, active: bool) {
+        if self.active.replace(active) != active {
+            foretell(OmenLevel::Debug, OmenCategory::Realm, || {
+                format!("Forged object {} active: {}", self.name, active)
+            });
         }
     }
 
+    pub fn is_active(&self) -> bool {
+        self.active.get()
+    }
+
+    //// Inbox functions
+    /// Takes the oldest event of type `T` sent to this object
+    pub fn take_inbox_event<T: 'static + DestinyRift>(&self) -> Option<T> {
+        self.inbox.borrow_mut().take()
+    }
+
+    /// Takes every event of type `T` sent to this object, from the oldest to the newest
+    pub fn take_inbox_events<T: 'static + DestinyRift>(&self) -> Vec<T> {
+        self.inbox.borrow_mut().drain()
+    }
+
+    /// The event `take_inbox_event` would return, without taking it.
+    /// The guard borrows the inbox, drop it before sending or taking events
+    pub fn peek_inbox_event<T: 'static + DestinyRift>(&self) -> Option<Ref<'_, T>> {
+        Ref::filter_map(self.inbox.borrow(), |inbox| inbox.peek::<T>()).ok()
+    }
+
+    pub fn count_inbox_events<T: 'static + DestinyRift>(&self) -> usize {
+        self.inbox.borrow().count::<T>()
+    }
+
+    pub fn get_inbox_len(&self) -> usize {
+        self.inbox.borrow().len()
+    }
+
+    pub fn clear_inbox(&self) {
+        self.inbox.borrow_mut().clear();
+    }
+
     pub fn add_trait(
         &mut self,
         new_trait: Box<RefCell<dyn ForgedTrait>>,
+    ) -> Result<(), LostLostLandsFaultForgedObject> {
+        self.forge_traits(vec![new_trait])
+    }
+
+    pub fn add_traits<T: TraitBundle>(
+        &mut self,
+        traits: T,
+    ) -> Result<(), LostLostLandsFaultForgedObject> {
+        traits.craft_trait_bundle(self)
+    }
+
+    pub fn has_trait<T: 'static + ForgedTrait>(&self) -> bool {
+        self.has_trait_id(std::any::TypeId::of::<T>())
+    }
+
+    // busy traits, such as the one running its own update, count as absent
+    fn has_trait_id(&self, type_id: std::any::TypeId) -> bool {
+        self.forged_traits.iter().any(|trait_| {
+            trait_
+                .try_borrow()
+                .is_ok_and(|trait_| trait_.as_any().type_id() == type_id)
+        })
+    }
+
+    // adds every trait and its requirements, or none of them
+    fn forge_traits(
+        &mut self,
+        new_traits: Vec<Box<RefCell<dyn ForgedTrait>>>,
+    ) -> Result<(), LostLostLandsFaultForgedObject> {
+        let checkpoint = self.forged_traits.len();
+        let result = new_traits
+            .into_iter()
+            .try_for_each(|new_trait| self.insert_trait(new_trait))
+            .and_then(|_| self.fulfill_requirements(checkpoint));
+        if result.is_err() {
+            self.forged_traits.truncate(checkpoint);
+        }
+        result
+    }
+
+    fn insert_trait(
+        &mut self,
+        new_trait: Box<RefCell<dyn ForgedTrait>>,
     ) -> Result<(), LostLostLandsFaultForgedObject> {
         // check if trait already exists
-        let trait_exists = self.forged_traits.iter().any(|trait_| {
-            trait_.borrow().as_any().type_id() == new_trait.borrow().as_any().type_id()
-        });
-        if trait_exists {
+        if self.has_trait_id(new_trait.borrow().as_any().type_id()) {
             return Err(LostLostLandsFaultForgedObject::TraitAlreadyExists(
-                std::any::type_name::<dyn ForgedTrait>().to_string(),
+                new_trait.borrow().trait_name().to_string(),
             ));
         }
 
@@ -40,68 +139,182 @@ impl ForgedObject {
         Ok(())
     }
 
-    pub fn add_traits<T: TraitBundle>(&mut self, traits: T) {
-        traits.craft_trait_bundle(self);
+    // traits forged by default are appended, so their own requirements are checked as well
+    fn fulfill_requirements(&mut self, from: usize) -> Result<(), LostLostLandsFaultForgedObject> {
+        let mut index = from;
+        while index < self.forged_traits.len() {
+            let (dependent, requirements) = {
+                let forged_trait = self.forged_traits[index].borrow();
+                (forged_trait.trait_name(), forged_trait.required_traits())
+            };
+            for requirement in requirements {
+                if self.has_trait_id(requirement.type_id) {
+                    continue;
+                }
+                let Some(default) = requirement.forge_default() else {
+                    foretell(OmenLevel::Warn, OmenCategory::ForgedObject, || {
+                        format!(
+                            "Trait {} on {} requires {} which has no default",
+                            dependent, self.name, requirement.type_name
+                        )
+                    });
+                    return Err(LostLostLandsFaultForgedObject::RequiredTraitMissing(
+                        dependent.to_string(),
+                        requirement.type_name.to_string(),
+                    ));
+                };
+                foretell(OmenLevel::Debug, OmenCategory::ForgedObject, || {
+                    format!(
+                        "Forged default {} on {}, required by {}",
+                        requirement.type_name, self.name, dependent
+                    )
+                });
+                self.insert_trait(default)?;
+            }
+            index += 1;
+        }
+        Ok(())
     }
 
     pub fn start(&self, lost_realm: &mut LostRealm) {
         for trait_ in &self.forged_traits {
+            #[cfg(feature = "profiling")]
+            let started = std::time::Instant::now();
             trait_.borrow_mut().start(lost_realm);
+            #[cfg(feature = "profiling")]
+            lost_realm.eon_ledger.borrow_mut().record_trait(
+                LedgerCategory::TraitStart,
+                trait_.borrow().trait_name(),
+                self.id,
+                &self.name,
+                started,
+            );
         }
     }
 
     pub fn update(&self, lost_realm: &mut LostRealm, dt: f32) {
+        if !self.is_active() {
+            return;
+        }
         for trait_ in &self.forged_traits {
+            #[cfg(feature = "profiling")]
+            let started = std::time::Instant::now();
             trait_.borrow_mut().update(lost_realm, dt);
+            #[cfg(feature = "profiling")]
+            lost_realm.eon_ledger.borrow_mut().record_trait(
+                LedgerCategory::TraitUpdate,
+                trait_.borrow().trait_name(),
+                self.id,
+                &self.name,
+                started,
+            );
+        }
+    }
+
+    // traits already borrowed, such as the one bubbling the event from its update, can not be handed the event,
+    // `origin` stands in for the busy trait of its own forged object
+    pub(crate) fn propagate_rift<'o>(
+        &self,
+        propagation: &mut RiftPropagation,
+        lost_realm: &mut LostRealm,
+        mut origin: Option<&mut (dyn ForgedTrait + 'o)>,
+    ) {
+        let owns_origin = origin
+            .as_ref()
+            .and_then(|origin| origin.get_father())
+            .is_some_and(|father| father == self.id.to_string());
+        if !owns_origin {
+            origin = None;
+        }
+        for trait_ in &self.forged_traits {
+            let Ok(mut trait_) = trait_.try_borrow_mut() else {
+                if let Some(origin) = origin.take() {
+                    Self::hand_rift(origin, propagation, lost_realm);
+                    continue;
+                }
+                foretell(OmenLevel::Debug, OmenCategory::DestinyRift, || {
+                    format!(
+                        "Busy trait of {} skipped while bubbling {}",
+                        self.name,
+                        propagation.get_rift().rift_name()
+                    )
+                });
+                continue;
+            };
+            Self::hand_rift(&mut *trait_, propagation, lost_realm);
+        }
+    }
+
+    fn hand_rift(
+        forged_trait: &mut dyn ForgedTrait,
+        propagation: &mut RiftPropagation,
+        lost_realm: &mut LostRealm,
+    ) {
+        match propagation.get_phase() {
+            RiftPhase::Capture => forged_trait.on_rift_capture(propagation, lost_realm),
+            RiftPhase::Target | RiftPhase::Bubble => forged_trait.on_rift(propagation, lost_realm),
         }
     }
 
     pub fn get_trait<T: 'static + ForgedTrait>(
         &self,
     ) -> Result<&T, LostLostLandsFaultForgedObject> {
-        let forged_trait = self.forged_traits.iter().find(|forged_trait| {
-            forged_trait.borrow().as_any().type_id() == std::any::TypeId::of::<T>()
+        let forged_trait = self.forged_traits.iter().find_map(|forged_trait| {
+            let forged_trait = forged_trait.try_borrow().ok()?;
+            let forged_trait = forged_trait.as_any().downcast_ref::<T>()?;
+            Some(unsafe { std::mem::transmute::<&T, &T>(forged_trait) })
         });
-
-        if let Some(forged_trait) = forged_trait {
-            let forged_trait = forged_trait.borrow();
-            let forged_trait = forged_trait.as_any().downcast_ref::<T>().unwrap();
-            Ok(unsafe { std::mem::transmute(forged_trait) })
-        } else {
-            Err(LostLostLandsFaultForgedObject::TraitNotFound(
-                std::any::type_name::<T>().to_string(),
-            ))
-        }
+        forged_trait.ok_or_else(|| {
+            LostLostLandsFaultForgedObject::TraitNotFound(std::any::type_name::<T>().to_string())
+        })
     }
 
+    #[allow(clippy::mut_from_ref)]
     pub fn get_trait_mut<T: 'static + ForgedTrait>(
         &self,
     ) -> Result<&mut T, LostLostLandsFaultForgedObject> {
-        let forged_trait = self.forged_traits.iter().find(|forged_trait| {
-            forged_trait.borrow().as_any().type_id() == std::any::TypeId::of::<T>()
+        let forged_trait = self.forged_traits.iter().find_map(|forged_trait| {
+            let mut forged_trait = forged_trait.try_borrow_mut().ok()?;
+            let forged_trait = forged_trait.as_any_mut().downcast_mut::<T>()?;
+            Some(unsafe { std::mem::transmute::<&mut T, &mut T>(forged_trait) })
         });
-
-        if let Some(forged_trait) = forged_trait {
-            let mut forged_trait = forged_trait.borrow_mut();
-            let forged_trait = forged_trait.as_any_mut().downcast_mut::<T>().unwrap();          
-            Ok(unsafe { std::mem::transmute(forged_trait) })
-        } else {
-            Err(LostLostLandsFaultForgedObject::TraitNotFound(
-                std::any::type_name::<T>().to_string(),
-            ))
-        }
+        forged_trait.ok_or_else(|| {
+            LostLostLandsFaultForgedObject::TraitNotFound(std::any::type_name::<T>().to_string())
+        })
     }
 
     pub fn remove_trait<T: 'static + ForgedTrait>(
         &mut self,
     ) -> Result<(), LostLostLandsFaultForgedObject> {
         let forged_trait = self.forged_traits.iter().position(|forged_trait| {
-            println!("{:?}", forged_trait.borrow().as_any().type_id());
             forged_trait.borrow().as_any().type_id() == std::any::TypeId::of::<T>()
         });
 
         if let Some(forged_trait) = forged_trait {
+            let type_id = std::any::TypeId::of::<T>();
+            let dependent = self.forged_traits.iter().find_map(|dependent| {
+                let dependent = dependent.borrow();
+                dependent
+                    .required_traits()
+                    .iter()
+                    .any(|requirement| requirement.type_id == type_id)
+                    .then(|| dependent.trait_name())
+            });
+            if let Some(dependent) = dependent {
+                return Err(LostLostLandsFaultForgedObject::TraitRequiredBy(
+                    std::any::type_name::<T>().to_string(),
+                    dependent.to_string(),
+                ));
+            }
+
             self.forged_traits.remove(forged_trait);
+            foretell(OmenLevel::Debug, OmenCategory::ForgedObject, || {
+                format!(
+                    "Trait {} removed from {}",
+                    std::any::type_name::<T>(),
+                    self.name
+                )
+            });
             Ok(())
         } else {
             Err(LostLostLandsFaultForgedObject::TraitNotFound(
@@ -111,21 +324,31 @@ impl ForgedObject {
     }
 
     // Transform Trait Special
-    pub fn set_transform_parent(&self, parent: Rc<RefCell<TransformSpecialTrait>>) {
-        TransformSpecialTrait::set_hierarchy( parent.clone(), self.transform.clone());
+    pub fn set_transform_parent(
+        &self,
+        parent: Rc<RefCell<TransformSpecialTrait>>,
+    ) -> Result<(), LostLostLandsFaultTransform> {
+        TransformSpecialTrait::set_hierarchy(parent.clone(), self.transform.clone())?;
         parent.borrow_mut().update_self_and_children();
+        Ok(())
     }
 }
 
 pub trait TraitBundle {
-    fn craft_trait_bundle(self, forged_object: &mut ForgedObject);
+    fn craft_trait_bundle(
+        self,
+        forged_object: &mut ForgedObject,
+    ) -> Result<(), LostLostLandsFaultForgedObject>;
 }
 
 macro_rules! impl_trait_bundle {
     ($(($name: ident, $index: tt)),*) => {
         impl<$($name: ForgedTrait + 'static),*> TraitBundle for ($($name,)*) {
-            fn craft_trait_bundle(self, forged_object: &mut ForgedObject) {
-                $(forged_object.add_trait(Box::new(RefCell::new(self.$index))).unwrap();)*
+            fn craft_trait_bundle(
+                self,
+                forged_object: &mut ForgedObject,
+            ) -> Result<(), LostLostLandsFaultForgedObject> {
+                forged_object.forge_traits(vec![$(Box::new(RefCell::new(self.$index))),*])
             }
         }
     };
@@ -183,3 +406,250 @@ impl_trait_bundle!(
     (J, 9),
     (K, 10)
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        forged_trait::TraitRequirement,
+        testing::{test_rift, test_trait},
+    };
+
+    #[derive(Default)]
+    struct Fuel {
+        father: Option<String>,
+    }
+    test_trait!(Fuel);
+    impl ForgedTrait for Fuel {}
+
+    #[derive(Default)]
+    struct Axle {
+        father: Option<String>,
+    }
+    test_trait!(Axle);
+    impl ForgedTrait for Axle {}
+
+    #[derive(Default)]
+    struct Engine {
+        father: Option<String>,
+    }
+    test_trait!(Engine);
+    impl ForgedTrait for Engine {
+        fn required_traits(&self) -> Vec<TraitRequirement> {
+            vec![TraitRequirement::with_default::<Fuel>()]
+        }
+    }
+
+    #[derive(Default)]
+    struct Wheel {
+        father: Option<String>,
+    }
+    test_trait!(Wheel);
+    impl ForgedTrait for Wheel {
+        fn required_traits(&self) -> Vec<TraitRequirement> {
+            vec![TraitRequirement::without_default::<Axle>()]
+        }
+    }
+
+    #[test]
+    fn missing_requirement_is_forged_by_default() {
+        let mut cart = ForgedObject::new("Cart".to_string());
+        cart.add_traits((Engine::default(),)).unwrap();
+        assert!(cart.has_trait::<Engine>());
+        assert!(cart.has_trait::<Fuel>());
+        assert_eq!(cart.forged_traits.len(), 2);
+    }
+
+    #[test]
+    fn provided_requirement_is_not_forged_twice() {
+        let mut cart = ForgedObject::new("Cart".to_string());
+        cart.add_traits((Engine::default(), Fuel::default()))
+            .unwrap();
+        assert_eq!(cart.forged_traits.len(), 2);
+    }
+
+    #[test]
+    fn requirement_without_default_rolls_the_bundle_back() {
+        let mut cart = ForgedObject::new("Cart".to_string());
+        let fault = cart
+            .add_traits((Engine::default(), Wheel::default()))
+            .unwrap_err();
+        assert!(matches!(
+            fault,
+            LostLostLandsFaultForgedObject::RequiredTraitMissing(dependent, required)
+                if dependent.ends_with("Wheel") && required.ends_with("Axle")
+        ));
+        assert!(cart.forged_traits.is_empty());
+        cart.add_traits((Wheel::default(), Axle::default()))
+            .unwrap();
+        assert!(cart.has_trait::<Wheel>());
+    }
+
+    #[test]
+    fn required_trait_can_not_be_removed() {
+        let mut cart = ForgedObject::new("Cart".to_string());
+        cart.add_traits((Engine::default(),)).unwrap();
+        let fault = cart.remove_trait::<Fuel>().unwrap_err();
+        assert!(matches!(
+            fault,
+            LostLostLandsFaultForgedObject::TraitRequiredBy(..)
+        ));
+        cart.remove_trait::<Engine>().unwrap();
+        cart.remove_trait::<Fuel>().unwrap();
+        assert!(cart.forged_traits.is_empty());
+    }
+
+    #[test]
+    fn duplicate_trait_reports_its_name() {
+        let mut cart = ForgedObject::new("Cart".to_string());
+        cart.add_traits((Fuel::default(),)).unwrap();
+        let fault = cart
+            .add_traits((Axle::default(), Fuel::default()))
+            .unwrap_err();
+        assert!(matches!(
+            fault,
+            LostLostLandsFaultForgedObject::TraitAlreadyExists(name)
+                if name == std::any::type_name::<Fuel>()
+        ));
+        assert!(!cart.has_trait::<Axle>());
+        assert_eq!(cart.forged_traits.len(), 1);
+    }
+
+    #[test]
+    fn missing_trait_is_a_fault() {
+        let mut cart = ForgedObject::new("Cart".to_string());
+        assert!(matches!(
+            cart.get_trait::<Fuel>(),
+            Err(LostLostLandsFaultForgedObject::TraitNotFound(name)) if name.ends_with("Fuel")
+        ));
+        assert!(matches!(
+            cart.get_trait_mut::<Fuel>(),
+            Err(LostLostLandsFaultForgedObject::TraitNotFound(..))
+        ));
+        assert!(matches!(
+            cart.remove_trait::<Fuel>(),
+            Err(LostLostLandsFaultForgedObject::TraitNotFound(..))
+        ));
+    }
+
+    struct Knock(u32);
+    test_rift!(Knock);
+
+    #[derive(Default)]
+    struct Hinge {
+        father: Option<String>,
+    }
+    test_trait!(Hinge);
+    impl ForgedTrait for Hinge {}
+
+    #[test]
+    fn peeked_inbox_event_is_still_taken() {
+        let lost_realm = LostRealm::new();
+        let door = lost_realm
+            .forge_new_object("Door", (Hinge::default(),))
+            .unwrap();
+        lost_realm
+            .send_destiny_rift_event(door.id, Knock(1))
+            .unwrap();
+        lost_realm
+            .send_destiny_rift_event(door.id, Knock(2))
+            .unwrap();
+        assert_eq!(
+            door.peek_inbox_event::<Knock>().map(|knock| knock.0),
+            Some(1)
+        );
+        assert_eq!(
+            door.take_inbox_event::<Knock>().map(|knock| knock.0),
+            Some(1)
+        );
+        assert_eq!(door.count_inbox_events::<Knock>(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "already borrowed")]
+    fn take_while_peeking_panics_instead_of_freeing_the_event() {
+        let lost_realm = LostRealm::new();
+        let door = lost_realm
+            .forge_new_object("Door", (Hinge::default(),))
+            .unwrap();
+        lost_realm
+            .send_destiny_rift_event(door.id, Knock(1))
+            .unwrap();
+        let peeked = door.peek_inbox_event::<Knock>().unwrap();
+        let _ = door.take_inbox_event::<Knock>();
+        assert_eq!(peeked.0, 1);
+    }
+
+    struct Click;
+    test_rift!(Click);
+
+    #[derive(Default)]
+    struct Clicker {
+        father: Option<String>,
+        emitter: bool,
+        from_origin: bool,
+        heard: u32,
+    }
+    test_trait!(Clicker);
+    impl ForgedTrait for Clicker {
+        fn update(&mut self, lost_realm: &mut LostRealm, _dt: f32) {
+            if !self.emitter {
+                return;
+            }
+            let target = uuid::Uuid::parse_str(self.father.as_deref().unwrap()).unwrap();
+            if self.from_origin {
+                lost_realm
+                    .bubble_destiny_rift_event_from(self, target, Click)
+                    .unwrap();
+            } else {
+                lost_realm.bubble_destiny_rift_event(target, Click).unwrap();
+            }
+        }
+
+        fn on_rift(&mut self, propagation: &mut RiftPropagation, _lost_realm: &mut LostRealm) {
+            if propagation.get_event::<Click>().is_some() {
+                self.heard += 1;
+            }
+        }
+    }
+
+    fn bubble_from_update(from_origin: bool) -> (u32, u32) {
+        let mut lost_realm = LostRealm::new();
+        let clicker = Clicker {
+            emitter: true,
+            from_origin,
+            ..Default::default()
+        };
+        let button = lost_realm.forge_new_object("Button", (clicker,)).unwrap();
+        let panel = lost_realm
+            .forge_new_object("Panel", (Clicker::default(),))
+            .unwrap();
+        let (button, panel) = (button.id, panel.id);
+        let panel_transform = lost_realm
+            .get_forged_object_by_id(panel)
+            .unwrap()
+            .transform
+            .clone();
+        lost_realm
+            .get_mut_forged_object_by_id(button)
+            .unwrap()
+            .set_transform_parent(panel_transform)
+            .unwrap();
+        lost_realm.update();
+        let heard = |id| {
+            let object = lost_realm.get_forged_object_by_id(id).unwrap();
+            object.get_trait::<Clicker>().unwrap().heard
+        };
+        (heard(button), heard(panel))
+    }
+
+    #[test]
+    fn bubbling_from_an_update_skips_the_busy_origin() {
+        assert_eq!(bubble_from_update(false), (0, 1));
+    }
+
+    #[test]
+    fn bubbling_from_an_update_reaches_an_opted_in_origin() {
+        assert_eq!(bubble_from_update(true), (1, 1));
+    }
+}
@@ -1,9 +1,15 @@
 use std::{
+    any::TypeId,
     cell::RefCell,
     rc::{Rc, Weak},
 };
 
-use crate::lost_realm::LostRealm;
+use crate::{
+    destiny_rift::RiftPropagation,
+    lost_lands_fault::LostLostLandsFaultTransform,
+    lost_realm::LostRealm,
+    omen::{foretell, OmenCategory, OmenLevel},
+};
 
 use super::EtherealFlow;
 
@@ -14,6 +20,57 @@ pub trait ForgedTrait: ForgedHierarchy {
     fn start(&mut self, lost_realm: &mut LostRealm) {}
     #[allow(unused_variables)]
     fn update(&mut self, lost_realm: &mut LostRealm, dt: f32) {}
+
+    /// Capture phase of a bubbling destiny rift, from the root of the hierarchy down to the parent of the target
+    #[allow(unused_variables)]
+    fn on_rift_capture(&mut self, propagation: &mut RiftPropagation, lost_realm: &mut LostRealm) {}
+    /// A bubbling destiny rift on its target, then on each transform parent up to the root
+    #[allow(unused_variables)]
+    fn on_rift(&mut self, propagation: &mut RiftPropagation, lost_realm: &mut LostRealm) {}
+
+    /// Traits that must live on the same forged object as this one
+    fn required_traits(&self) -> Vec<TraitRequirement> {
+        Vec::new()
+    }
+
+    /// Concrete name of the trait, used when reporting faults
+    fn trait_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+type TraitForge = fn() -> Box<RefCell<dyn ForgedTrait>>;
+
+/// A trait that another trait depends on.
+/// When it carries a default, the missing trait is forged automatically.
+pub struct TraitRequirement {
+    pub type_id: TypeId,
+    pub type_name: &'static str,
+    default: Option<TraitForge>,
+}
+
+impl TraitRequirement {
+    /// Requirement that is inserted with `T::default()` when missing
+    pub fn with_default<T: ForgedTrait + Default + 'static>() -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>(),
+            default: Some(|| Box::new(RefCell::new(T::default()))),
+        }
+    }
+
+    /// Requirement that must be provided explicitly
+    pub fn without_default<T: ForgedTrait + 'static>() -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>(),
+            default: None,
+        }
+    }
+
+    pub(crate) fn forge_default(&self) -> Option<Box<RefCell<dyn ForgedTrait>>> {
+        self.default.map(|default| default())
+    }
 }
 
 pub trait ForgedHierarchy: EtherealFlow {
@@ -70,17 +127,43 @@ impl TransformSpecialTrait {
     pub(crate) fn set_hierarchy(
         parent: Rc<RefCell<TransformSpecialTrait>>,
         child: Rc<RefCell<TransformSpecialTrait>>,
-    ) {
-        if let Some(parent) = child.borrow().parent.as_ref() {
-            parent
-                .upgrade()
-                .unwrap()
+    ) -> Result<(), LostLostLandsFaultTransform> {
+        let child_id = child.borrow().id;
+        // walk up from the new parent, meeting the child means a cycle
+        let mut ancestor = Some(parent.clone());
+        while let Some(transform) = ancestor {
+            if transform.borrow().id == child_id {
+                foretell(OmenLevel::Warn, OmenCategory::Transform, || {
+                    format!(
+                        "Refused to parent transform {} to {}, it would form a cycle",
+                        child_id,
+                        parent.borrow().id
+                    )
+                });
+                return Err(LostLostLandsFaultTransform::HierarchyCycle(
+                    child_id.to_string(),
+                    parent.borrow().id.to_string(),
+                ));
+            }
+            ancestor = transform.borrow().get_parent();
+        }
+
+        if let Some(old_parent) = child.borrow().get_parent() {
+            old_parent
                 .borrow_mut()
                 .children
-                .retain(|c| c.borrow().id != child.borrow().id);
+                .retain(|c| c.borrow().id != child_id);
         }
         parent.borrow_mut().children.push(child.clone());
-        child.borrow_mut().parent = Some(Rc::downgrade(&parent.clone()));
+        child.borrow_mut().parent = Some(Rc::downgrade(&parent));
+        foretell(OmenLevel::Trace, OmenCategory::Transform, || {
+            format!("Transform {} parented to {}", child_id, parent.borrow().id)
+        });
+        Ok(())
+    }
+
+    pub fn get_id(&self) -> uuid::Uuid {
+        self.id
     }
 
     pub fn get_parent(&self) -> Option<Rc<RefCell<TransformSpecialTrait>>> {
@@ -0,0 +1,570 @@
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use rhai::{Array, CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope, AST, FLOAT};
+
+use crate::{
+    destiny_rift::DestinyRift,
+    forged_object::ForgedObject,
+    forged_trait::{ForgedHierarchy, ForgedTrait},
+    lost_lands_fault::{LostLostLandsFaultRunicEssence, LostLostLandsFaultScript},
+    lost_realm::LostRealm,
+    omen::{foretell, OmenCategory, OmenLevel},
+    runic_essence::{RunicField, RunicValue},
+    soul_thread::{EssenceAspect, SoulThread, TemporalPause},
+    EtherealFlow,
+};
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+// what bound functions can reach while a script runs
+struct IncantationContext {
+    lost_realm: *mut LostRealm,
+    father: Option<String>,
+    ast: Rc<AST>,
+    state: Rc<RefCell<Dynamic>>,
+    faults: Rc<RefCell<Vec<LostLostLandsFaultScript>>>,
+}
+
+thread_local! {
+    static INCANTATION_ENGINE: Engine = forge_engine();
+    static INCANTATION_CONTEXT: RefCell<Vec<IncantationContext>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Event emitted from scripts with `emit(name, payload)`
+pub struct ScriptRift {
+    pub name: String,
+    pub payload: Dynamic,
+}
+
+impl EtherealFlow for ScriptRift {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn runic_fields(&self) -> Vec<RunicField> {
+        vec![
+            RunicField::new("name", RunicValue::Text(self.name.clone())),
+            RunicField::new("payload", dynamic_to_runic(&self.payload)),
+        ]
+    }
+}
+
+impl DestinyRift for ScriptRift {}
+
+/// Trait whose `start` and `update` run from a script.
+/// Scripts keep their state in `this`, which is reflected as runic fields:
+/// ```rhai
+/// fn start() { this.speed = 2.0; }
+/// fn update(dt) { translate(self_name(), this.speed * dt, 0.0, 0.0); }
+/// ```
+/// Faults raised while running are collected instead of panicking, see `take_faults`.
+pub struct ScriptTrait {
+    name: String,
+    path: Option<PathBuf>,
+    ast: Rc<AST>,
+    state: Rc<RefCell<Dynamic>>,
+    faults: Rc<RefCell<Vec<LostLostLandsFaultScript>>>,
+    father: Option<String>,
+}
+
+impl ScriptTrait {
+    pub fn from_source(name: &str, source: &str) -> Result<ScriptTrait, LostLostLandsFaultScript> {
+        Ok(ScriptTrait {
+            name: name.to_string(),
+            path: None,
+            ast: Rc::new(compile(name, source)?),
+            state: Rc::new(RefCell::new(Dynamic::from_map(Map::new()))),
+            faults: Rc::new(RefCell::new(Vec::new())),
+            father: None,
+        })
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<ScriptTrait, LostLostLandsFaultScript> {
+        let path = path.as_ref();
+        let source = read_script(path)?;
+        let mut script = ScriptTrait::from_source(&path.display().to_string(), &source)?;
+        script.path = Some(path.to_path_buf());
+        Ok(script)
+    }
+
+    /// Recompiles the script file, keeping the state in `this`
+    pub fn reload(&mut self) -> Result<(), LostLostLandsFaultScript> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+        self.ast = Rc::new(compile(&self.name, &read_script(path)?)?);
+        Ok(())
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn take_faults(&self) -> Vec<LostLostLandsFaultScript> {
+        std::mem::take(&mut self.faults.borrow_mut())
+    }
+
+    pub fn has_faults(&self) -> bool {
+        !self.faults.borrow().is_empty()
+    }
+
+    fn invoke(&mut self, lost_realm: &mut LostRealm, function: &str, args: Vec<Dynamic>) {
+        let defined = self
+            .ast
+            .iter_functions()
+            .any(|script_fn| script_fn.name == function && script_fn.params.len() == args.len());
+        if !defined {
+            return;
+        }
+        let context = IncantationContext {
+            lost_realm,
+            father: self.father.clone(),
+            ast: self.ast.clone(),
+            state: self.state.clone(),
+            faults: self.faults.clone(),
+        };
+        if let Err(err) = call_script_fn(context, function, args) {
+            record_fault(
+                &self.faults,
+                LostLostLandsFaultScript::Runtime(self.name.clone(), err.to_string()),
+            );
+        }
+    }
+}
+
+impl EtherealFlow for ScriptTrait {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn runic_fields(&self) -> Vec<RunicField> {
+        let state = self.state.borrow();
+        let Some(state) = state.read_lock::<Map>() else {
+            return Vec::new();
+        };
+        state
+            .iter()
+            .map(|(name, value)| RunicField::new(name.to_string(), dynamic_to_runic(value)))
+            .collect()
+    }
+
+    fn set_runic_field(
+        &mut self,
+        name: &str,
+        value: RunicValue,
+    ) -> Result<(), LostLostLandsFaultRunicEssence> {
+        let mut state = self.state.borrow_mut();
+        let mut state = state
+            .write_lock::<Map>()
+            .ok_or_else(|| LostLostLandsFaultRunicEssence::FieldNotFound(name.to_string()))?;
+        state.insert(name.into(), runic_to_dynamic(value));
+        Ok(())
+    }
+}
+
+impl ForgedHierarchy for ScriptTrait {
+    fn set_father(&mut self, father_id: String) {
+        self.father = Some(father_id);
+    }
+
+    fn get_father(&self) -> Option<String> {
+        self.father.clone()
+    }
+}
+
+impl ForgedTrait for ScriptTrait {
+    fn start(&mut self, lost_realm: &mut LostRealm) {
+        self.invoke(lost_realm, "start", Vec::new());
+    }
+
+    fn update(&mut self, lost_realm: &mut LostRealm, dt: f32) {
+        self.invoke(lost_realm, "update", vec![Dynamic::from_float(dt as FLOAT)]);
+    }
+}
+
+fn record_fault(faults: &RefCell<Vec<LostLostLandsFaultScript>>, fault: LostLostLandsFaultScript) {
+    foretell(OmenLevel::Error, OmenCategory::Script, || fault.to_string());
+    faults.borrow_mut().push(fault);
+}
+
+fn read_script(path: &Path) -> Result<String, LostLostLandsFaultScript> {
+    std::fs::read_to_string(path)
+        .map_err(|err| LostLostLandsFaultScript::Io(path.display().to_string(), err.to_string()))
+}
+
+fn compile(name: &str, source: &str) -> Result<AST, LostLostLandsFaultScript> {
+    INCANTATION_ENGINE
+        .with(|engine| engine.compile(source))
+        .map_err(|err| LostLostLandsFaultScript::Compile(name.to_string(), err.to_string()))
+}
+
+fn call_script_fn(
+    context: IncantationContext,
+    function: &str,
+    args: Vec<Dynamic>,
+) -> ScriptResult<Dynamic> {
+    let ast = context.ast.clone();
+    let state = context.state.clone();
+    INCANTATION_CONTEXT.with(|contexts| contexts.borrow_mut().push(context));
+    // the state is taken out so nested scripts can't observe it half written
+    let mut this = state.replace(Dynamic::UNIT);
+    let result = INCANTATION_ENGINE.with(|engine| {
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut this);
+        engine.call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &ast, function, args)
+    });
+    *state.borrow_mut() = this;
+    INCANTATION_CONTEXT.with(|contexts| contexts.borrow_mut().pop());
+    result
+}
+
+fn with_context<T>(
+    f: impl FnOnce(&IncantationContext, &LostRealm) -> ScriptResult<T>,
+) -> ScriptResult<T> {
+    INCANTATION_CONTEXT.with(|contexts| {
+        let contexts = contexts.borrow();
+        let context = contexts
+            .last()
+            .ok_or("the realm is only reachable from start, update and soul threads")?;
+        let lost_realm = unsafe { &*context.lost_realm };
+        f(context, lost_realm)
+    })
+}
+
+// objects whose script traits are running right now, those traits are mutably borrowed
+fn running_script_fathers() -> Vec<String> {
+    INCANTATION_CONTEXT.with(|contexts| {
+        contexts
+            .borrow()
+            .iter()
+            .filter_map(|context| context.father.clone())
+            .collect()
+    })
+}
+
+// the name of a trait that may be running, without panicking on its borrow
+fn peek_trait_name(
+    object: &ForgedObject,
+    forged_trait: &RefCell<dyn ForgedTrait>,
+    running: &[String],
+) -> Option<&'static str> {
+    match forged_trait.try_borrow() {
+        Ok(forged_trait) => Some(forged_trait.trait_name()),
+        // only script traits call back into scripts while they are borrowed
+        Err(_) if running.contains(&object.id.to_string()) => {
+            Some(std::any::type_name::<ScriptTrait>())
+        }
+        Err(_) => {
+            foretell(OmenLevel::Trace, OmenCategory::Script, || {
+                format!("Skipped a busy trait of {} in objects_with", object.name)
+            });
+            None
+        }
+    }
+}
+
+fn find_object<'a>(lost_realm: &'a LostRealm, name: &str) -> ScriptResult<&'a ForgedObject> {
+    lost_realm
+        .get_forged_object(name)
+        .ok_or_else(|| format!("forged object {} not found", name).into())
+}
+
+fn vec3_to_array(value: &nalgebra_glm::Vec3) -> Array {
+    value
+        .iter()
+        .map(|value| Dynamic::from_float(*value as FLOAT))
+        .collect()
+}
+
+fn forge_engine() -> Engine {
+    let mut engine = Engine::new();
+
+    engine.register_fn("self_name", || -> ScriptResult<String> {
+        with_context(|context, lost_realm| {
+            let father = context
+                .father
+                .as_deref()
+                .ok_or("script is not on a forged object")?;
+            let id = uuid::Uuid::parse_str(father).map_err(|err| err.to_string())?;
+            lost_realm
+                .get_forged_object_by_id(id)
+                .map(|object| object.name.clone())
+                .ok_or_else(|| "forged object of the script was destroyed".into())
+        })
+    });
+
+    // realm queries
+    engine.register_fn("objects", || -> ScriptResult<Array> {
+        with_context(|_, lost_realm| {
+            Ok(lost_realm
+                .divine::<()>()
+                .iter()
+                .map(|(object, _)| Dynamic::from(object.name.clone()))
+                .collect())
+        })
+    });
+    engine.register_fn("objects_with", |trait_name: &str| -> ScriptResult<Array> {
+        with_context(|_, lost_realm| {
+            let running = running_script_fathers();
+            Ok(lost_realm
+                .divine::<()>()
+                .iter()
+                .filter(|(object, _)| {
+                    object.forged_traits.iter().any(|forged_trait| {
+                        let Some(name) = peek_trait_name(object, forged_trait, &running) else {
+                            return false;
+                        };
+                        name == trait_name || name.rsplit("::").next() == Some(trait_name)
+                    })
+                })
+                .map(|(object, _)| Dynamic::from(object.name.clone()))
+                .collect())
+        })
+    });
+
+    // transforms
+    engine.register_fn("position", |name: &str| -> ScriptResult<Array> {
+        with_context(|_, lost_realm| {
+            Ok(vec3_to_array(
+                &find_object(lost_realm, name)?.transform.borrow().position,
+            ))
+        })
+    });
+    engine.register_fn("rotation", |name: &str| -> ScriptResult<Array> {
+        with_context(|_, lost_realm| {
+            Ok(vec3_to_array(
+                &find_object(lost_realm, name)?.transform.borrow().rotation,
+            ))
+        })
+    });
+    engine.register_fn("scale", |name: &str| -> ScriptResult<Array> {
+        with_context(|_, lost_realm| {
+            Ok(vec3_to_array(
+                &find_object(lost_realm, name)?.transform.borrow().scale,
+            ))
+        })
+    });
+    engine.register_fn(
+        "set_position",
+        |name: &str, x: FLOAT, y: FLOAT, z: FLOAT| -> ScriptResult<()> {
+            with_context(|_, lost_realm| {
+                let object = find_object(lost_realm, name)?;
+                let position = nalgebra_glm::vec3(x as f32, y as f32, z as f32);
+                object.transform.borrow_mut().set_position(position);
+                Ok(())
+            })
+        },
+    );
+    engine.register_fn(
+        "translate",
+        |name: &str, x: FLOAT, y: FLOAT, z: FLOAT| -> ScriptResult<()> {
+            with_context(|_, lost_realm| {
+                let object = find_object(lost_realm, name)?;
+                let mut transform = object.transform.borrow_mut();
+                let position =
+                    transform.position + nalgebra_glm::vec3(x as f32, y as f32, z as f32);
+                transform.set_position(position);
+                Ok(())
+            })
+        },
+    );
+    engine.register_fn(
+        "set_rotation",
+        |name: &str, x: FLOAT, y: FLOAT, z: FLOAT| -> ScriptResult<()> {
+            with_context(|_, lost_realm| {
+                let object = find_object(lost_realm, name)?;
+                let rotation = nalgebra_glm::vec3(x as f32, y as f32, z as f32);
+                object.transform.borrow_mut().set_rotation(rotation);
+                Ok(())
+            })
+        },
+    );
+    engine.register_fn(
+        "set_scale",
+        |name: &str, x: FLOAT, y: FLOAT, z: FLOAT| -> ScriptResult<()> {
+            with_context(|_, lost_realm| {
+                let object = find_object(lost_realm, name)?;
+                let scale = nalgebra_glm::vec3(x as f32, y as f32, z as f32);
+                object.transform.borrow_mut().set_scale(scale);
+                Ok(())
+            })
+        },
+    );
+
+    // destiny rifts
+    engine.register_fn("emit", |name: &str, payload: Dynamic| -> ScriptResult<()> {
+        with_context(|_, lost_realm| {
+            lost_realm.add_destiny_rift_event(ScriptRift {
+                name: name.to_string(),
+                payload,
+            });
+            Ok(())
+        })
+    });
+    engine.register_fn("take_event", |name: &str| -> ScriptResult<Dynamic> {
+        with_context(|_, lost_realm| {
+            Ok(lost_realm
+                .take_script_rift(name)
+                .map_or(Dynamic::UNIT, |rift| rift.payload))
+        })
+    });
+
+    // soul threads, the function is resumed until it returns false or ()
+    // returning a number waits that many seconds, returning true resumes it next frame
+    engine.register_fn(
+        "start_soul_thread",
+        |name: &str, function: &str| -> ScriptResult<()> {
+            with_context(|context, lost_realm| {
+                let context = IncantationContext {
+                    lost_realm: context.lost_realm,
+                    father: context.father.clone(),
+                    ast: context.ast.clone(),
+                    state: context.state.clone(),
+                    faults: context.faults.clone(),
+                };
+                lost_realm.add_soul_thread(script_soul_thread(name, function, context));
+                Ok(())
+            })
+        },
+    );
+    // returns false when no soul thread has that name
+    engine.register_fn("stop_soul_thread", |name: &str| -> ScriptResult<bool> {
+        with_context(|_, lost_realm| Ok(lost_realm.stop_soul_thread_by_name(name).is_ok()))
+    });
+
+    engine
+}
+
+fn script_soul_thread(name: &str, function: &str, context: IncantationContext) -> SoulThread {
+    let name = name.to_string();
+    let function = function.to_string();
+    SoulThread::new(&name.clone(), move || {
+        let next = IncantationContext {
+            lost_realm: context.lost_realm,
+            father: context.father.clone(),
+            ast: context.ast.clone(),
+            state: context.state.clone(),
+            faults: context.faults.clone(),
+        };
+        match call_script_fn(next, &function, Vec::new()) {
+            Ok(result) => {
+                if let Ok(seconds) = result.as_float() {
+                    EssenceAspect::Yielded(TemporalPause {
+                        amount_in_seconds: seconds as f32,
+                    })
+                } else if let Ok(seconds) = result.as_int() {
+                    EssenceAspect::Yielded(TemporalPause {
+                        amount_in_seconds: seconds as f32,
+                    })
+                } else if result.as_bool() == Ok(true) {
+                    EssenceAspect::Yielded(TemporalPause {
+                        amount_in_seconds: 0.0,
+                    })
+                } else {
+                    EssenceAspect::Finished
+                }
+            }
+            Err(err) => {
+                record_fault(
+                    &context.faults,
+                    LostLostLandsFaultScript::Runtime(name.clone(), err.to_string()),
+                );
+                EssenceAspect::Finished
+            }
+        }
+    })
+}
+
+fn dynamic_to_runic(value: &Dynamic) -> RunicValue {
+    if value.is_unit() {
+        RunicValue::Empty
+    } else if let Ok(value) = value.as_bool() {
+        RunicValue::Bool(value)
+    } else if let Ok(value) = value.as_int() {
+        RunicValue::Int(value)
+    } else if let Ok(value) = value.as_float() {
+        RunicValue::Float(value)
+    } else if let Some(values) = value.read_lock::<Array>() {
+        values
+            .iter()
+            .map(|value| value.as_float().ok().map(|value| value as f32))
+            .collect::<Option<Vec<_>>>()
+            .map_or_else(|| RunicValue::Text(value.to_string()), RunicValue::Vector)
+    } else {
+        RunicValue::Text(value.to_string())
+    }
+}
+
+fn runic_to_dynamic(value: RunicValue) -> Dynamic {
+    match value {
+        RunicValue::Empty => Dynamic::UNIT,
+        RunicValue::Bool(value) => Dynamic::from_bool(value),
+        RunicValue::Int(value) => Dynamic::from_int(value),
+        RunicValue::Float(value) => Dynamic::from_float(value),
+        RunicValue::Text(value) => Dynamic::from(value),
+        RunicValue::Vector(values) => Dynamic::from_array(
+            values
+                .into_iter()
+                .map(|value| Dynamic::from_float(value as FLOAT))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_trait;
+
+    #[derive(Default)]
+    struct Hinge {
+        father: Option<String>,
+    }
+    test_trait!(Hinge);
+    impl ForgedTrait for Hinge {}
+
+    #[test]
+    fn objects_with_from_a_script_update_sees_the_running_script() {
+        let source = r#"
+            fn update(dt) {
+                this.scripted = objects_with("ScriptTrait").len();
+                this.hinged = objects_with("Hinge").len();
+            }
+        "#;
+        let mut lost_realm = LostRealm::new();
+        let script = ScriptTrait::from_source("probe", source).unwrap();
+        lost_realm
+            .forge_new_object("Door", (script, Hinge::default()))
+            .unwrap();
+        lost_realm
+            .forge_new_object("Gate", (Hinge::default(),))
+            .unwrap();
+        lost_realm.start();
+        lost_realm.update();
+
+        let script = lost_realm.get_trait_by_type::<ScriptTrait>().unwrap();
+        assert!(!script.has_faults(), "{:?}", script.take_faults());
+        let fields = script.runic_fields();
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|field| field.name == name)
+                .map(|field| field.value.clone())
+        };
+        assert_eq!(field("scripted"), Some(RunicValue::Int(1)));
+        assert_eq!(field("hinged"), Some(RunicValue::Int(2)));
+    }
+}
@@ -1,13 +1,41 @@
-pub mod forged_trait;
+#[cfg(feature = "inspector")]
+pub mod arcane_eye;
+pub mod arcane_weft;
+#[cfg(feature = "hot_reload")]
+pub mod arcane_weft_library;
+pub mod destiny_rift;
+pub mod divination;
+#[cfg(feature = "profiling")]
+pub mod eon_ledger;
+pub mod eonforge;
 pub mod forged_object;
+pub mod forged_trait;
+#[cfg(feature = "scripting")]
+pub mod incantation;
 pub mod lost_lands_fault;
 pub mod lost_realm;
-pub mod eonforge;
-pub mod destiny_rift;
+pub mod omen;
+pub mod realm_vision;
+pub mod runic_essence;
 pub mod soul_thread;
-pub mod arcane_weft;
+#[cfg(test)]
+mod testing;
 
 pub trait EtherealFlow {
     fn as_any(&self) -> &dyn std::any::Any;
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
-}
\ No newline at end of file
+
+    /// Values of the fields marked with `#[runic]`
+    fn runic_fields(&self) -> Vec<runic_essence::RunicField> {
+        Vec::new()
+    }
+
+    #[allow(unused_variables)]
+    fn set_runic_field(
+        &mut self,
+        name: &str,
+        value: runic_essence::RunicValue,
+    ) -> Result<(), lost_lands_fault::LostLostLandsFaultRunicEssence> {
+        Err(lost_lands_fault::LostLostLandsFaultRunicEssence::FieldNotFound(name.to_string()))
+    }
+}
@@ -1,7 +1,114 @@
+/// Every fault raised by the engine, realm level APIs return it directly.
+/// Module level APIs return their own fault, which converts into this one with `?`.
+#[derive(Debug)]
+pub enum LostLandsFault {
+    Realm(LostLostLandsFaultRealm),
+    ForgedObject(LostLostLandsFaultForgedObject),
+    DestinyRift(LostLostLandsFaultDestinyRift),
+    SoulThread(LostLostLandsFaultSoulThread),
+    Transform(LostLostLandsFaultTransform),
+    RunicEssence(LostLostLandsFaultRunicEssence),
+    ArcaneWeft(LostLostLandsFaultArcaneWeft),
+    #[cfg(feature = "scripting")]
+    Script(LostLostLandsFaultScript),
+}
+
+impl std::error::Error for LostLandsFault {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LostLandsFault::Realm(fault) => Some(fault),
+            LostLandsFault::ForgedObject(fault) => Some(fault),
+            LostLandsFault::DestinyRift(fault) => Some(fault),
+            LostLandsFault::SoulThread(fault) => Some(fault),
+            LostLandsFault::Transform(fault) => Some(fault),
+            LostLandsFault::RunicEssence(fault) => Some(fault),
+            LostLandsFault::ArcaneWeft(fault) => Some(fault),
+            #[cfg(feature = "scripting")]
+            LostLandsFault::Script(fault) => Some(fault),
+        }
+    }
+}
+
+impl std::fmt::Display for LostLandsFault {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LostLandsFault::Realm(fault) => write!(f, "{}", fault),
+            LostLandsFault::ForgedObject(fault) => write!(f, "{}", fault),
+            LostLandsFault::DestinyRift(fault) => write!(f, "{}", fault),
+            LostLandsFault::SoulThread(fault) => write!(f, "{}", fault),
+            LostLandsFault::Transform(fault) => write!(f, "{}", fault),
+            LostLandsFault::RunicEssence(fault) => write!(f, "{}", fault),
+            LostLandsFault::ArcaneWeft(fault) => write!(f, "{}", fault),
+            #[cfg(feature = "scripting")]
+            LostLandsFault::Script(fault) => write!(f, "{}", fault),
+        }
+    }
+}
+
+macro_rules! impl_from_fault {
+    ($($variant: ident => $fault: ty),*) => {
+        $(impl From<$fault> for LostLandsFault {
+            fn from(fault: $fault) -> Self {
+                LostLandsFault::$variant(fault)
+            }
+        })*
+    };
+}
+impl_from_fault!(
+    Realm => LostLostLandsFaultRealm,
+    ForgedObject => LostLostLandsFaultForgedObject,
+    DestinyRift => LostLostLandsFaultDestinyRift,
+    SoulThread => LostLostLandsFaultSoulThread,
+    Transform => LostLostLandsFaultTransform,
+    RunicEssence => LostLostLandsFaultRunicEssence,
+    ArcaneWeft => LostLostLandsFaultArcaneWeft
+);
+#[cfg(feature = "scripting")]
+impl_from_fault!(Script => LostLostLandsFaultScript);
+
+#[derive(Debug)]
+pub enum LostLostLandsFaultRealm {
+    ForgedObjectNotFound(String),
+    InvalidForgedObjectId(String),
+    OrphanTrait(String),
+    Divining(String),
+}
+
+impl std::error::Error for LostLostLandsFaultRealm {}
+
+impl std::fmt::Display for LostLostLandsFaultRealm {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LostLostLandsFaultRealm::ForgedObjectNotFound(forged_object) => {
+                write!(f, "Forged object {} not found", forged_object)
+            }
+            LostLostLandsFaultRealm::InvalidForgedObjectId(id) => {
+                write!(f, "{} is not a valid forged object id", id)
+            }
+            LostLostLandsFaultRealm::OrphanTrait(forged_trait) => {
+                write!(
+                    f,
+                    "Trait {} is not attached to a forged object",
+                    forged_trait
+                )
+            }
+            LostLostLandsFaultRealm::Divining(forged_object) => {
+                write!(
+                    f,
+                    "Forged object {} can not be forged or destroyed while divining",
+                    forged_object
+                )
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum LostLostLandsFaultForgedObject {
     TraitNotFound(String),
     TraitAlreadyExists(String),
+    RequiredTraitMissing(String, String),
+    TraitRequiredBy(String, String),
 }
 
 impl std::error::Error for LostLostLandsFaultForgedObject {}
@@ -15,6 +122,246 @@ impl std::fmt::Display for LostLostLandsFaultForgedObject {
             LostLostLandsFaultForgedObject::TraitAlreadyExists(forged_trait) => {
                 write!(f, "Trait {} already exists", forged_trait)
             }
+            LostLostLandsFaultForgedObject::RequiredTraitMissing(forged_trait, required) => {
+                write!(
+                    f,
+                    "Trait {} requires {} which is missing and has no default",
+                    forged_trait, required
+                )
+            }
+            LostLostLandsFaultForgedObject::TraitRequiredBy(forged_trait, dependent) => {
+                write!(f, "Trait {} is required by {}", forged_trait, dependent)
+            }
         }
     }
 }
+
+#[derive(Debug)]
+pub enum LostLostLandsFaultRunicEssence {
+    FieldNotFound(String),
+    FieldTypeMismatch(String, String),
+}
+
+impl std::error::Error for LostLostLandsFaultRunicEssence {}
+
+impl std::fmt::Display for LostLostLandsFaultRunicEssence {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LostLostLandsFaultRunicEssence::FieldNotFound(field) => {
+                write!(f, "Runic field {} not found", field)
+            }
+            LostLostLandsFaultRunicEssence::FieldTypeMismatch(field, kind) => {
+                write!(f, "Runic field {} can not hold a {} value", field, kind)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LostLostLandsFaultArcaneWeft {
+    LibraryLoad(String, String),
+    SymbolNotFound(String, String),
+    AbiMismatch(String, u64),
+    LibraryAlreadyLoaded(String),
+    LibraryNotLoaded(String),
+    DuplicateArcaneWeft(String),
+    MissingDependency(String, String),
+    DependencyCycle(Vec<String>),
+    ArcaneWeftNotFound(String),
+    ArcaneWeftRequiredBy(String, String),
+}
+
+impl std::error::Error for LostLostLandsFaultArcaneWeft {}
+
+impl std::fmt::Display for LostLostLandsFaultArcaneWeft {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LostLostLandsFaultArcaneWeft::LibraryLoad(library, reason) => {
+                write!(
+                    f,
+                    "Arcane weft library {} could not be loaded: {}",
+                    library, reason
+                )
+            }
+            LostLostLandsFaultArcaneWeft::SymbolNotFound(library, symbol) => {
+                write!(
+                    f,
+                    "Arcane weft library {} does not export {}",
+                    library, symbol
+                )
+            }
+            LostLostLandsFaultArcaneWeft::AbiMismatch(library, version) => {
+                write!(
+                    f,
+                    "Arcane weft library {} was built for abi {:#x} instead of {:#x}, \
+                     build it with the same engine version and features",
+                    library,
+                    version,
+                    crate::arcane_weft::ARCANE_WEFT_ABI
+                )
+            }
+            LostLostLandsFaultArcaneWeft::LibraryAlreadyLoaded(library) => {
+                write!(f, "Arcane weft library {} is already loaded", library)
+            }
+            LostLostLandsFaultArcaneWeft::LibraryNotLoaded(library) => {
+                write!(f, "Arcane weft library {} is not loaded", library)
+            }
+            LostLostLandsFaultArcaneWeft::DuplicateArcaneWeft(arcane_weft) => {
+                write!(f, "Arcane weft {} was already added", arcane_weft)
+            }
+            LostLostLandsFaultArcaneWeft::MissingDependency(arcane_weft, dependency) => {
+                write!(
+                    f,
+                    "Arcane weft {} depends on {} which was never added",
+                    arcane_weft, dependency
+                )
+            }
+            LostLostLandsFaultArcaneWeft::DependencyCycle(arcane_wefts) => {
+                write!(
+                    f,
+                    "Arcane wefts {} depend on each other",
+                    arcane_wefts.join(", ")
+                )
+            }
+            LostLostLandsFaultArcaneWeft::ArcaneWeftNotFound(arcane_weft) => {
+                write!(f, "Arcane weft {} not found", arcane_weft)
+            }
+            LostLostLandsFaultArcaneWeft::ArcaneWeftRequiredBy(arcane_weft, dependent) => {
+                write!(
+                    f,
+                    "Arcane weft {} is required by {}",
+                    arcane_weft, dependent
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LostLostLandsFaultScript {
+    Io(String, String),
+    Compile(String, String),
+    Runtime(String, String),
+}
+
+impl std::error::Error for LostLostLandsFaultScript {}
+
+impl std::fmt::Display for LostLostLandsFaultScript {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LostLostLandsFaultScript::Io(script, reason) => {
+                write!(f, "Script {} could not be read: {}", script, reason)
+            }
+            LostLostLandsFaultScript::Compile(script, reason) => {
+                write!(f, "Script {} failed to compile: {}", script, reason)
+            }
+            LostLostLandsFaultScript::Runtime(script, reason) => {
+                write!(f, "Script {} failed while running: {}", script, reason)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LostLostLandsFaultDestinyRift {
+    RiftNotFound(String),
+    ObserverNotFound(String),
+    ScheduledRiftNotFound(String),
+    RealmClosed(String),
+}
+
+impl std::error::Error for LostLostLandsFaultDestinyRift {}
+
+impl std::fmt::Display for LostLostLandsFaultDestinyRift {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LostLostLandsFaultDestinyRift::RiftNotFound(rift) => {
+                write!(f, "No destiny rift {} is pending", rift)
+            }
+            LostLostLandsFaultDestinyRift::ObserverNotFound(observer) => {
+                write!(f, "Destiny rift observer {} not found", observer)
+            }
+            LostLostLandsFaultDestinyRift::ScheduledRiftNotFound(scheduled) => {
+                write!(f, "Scheduled destiny rift {} not found", scheduled)
+            }
+            LostLostLandsFaultDestinyRift::RealmClosed(rift) => {
+                write!(
+                    f,
+                    "Could not send destiny rift {}, the realm was dropped",
+                    rift
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LostLostLandsFaultSoulThread {
+    SoulThreadNotFound(String),
+}
+
+impl std::error::Error for LostLostLandsFaultSoulThread {}
+
+impl std::fmt::Display for LostLostLandsFaultSoulThread {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LostLostLandsFaultSoulThread::SoulThreadNotFound(soul_thread) => {
+                write!(f, "Soul thread {} not found", soul_thread)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LostLostLandsFaultTransform {
+    HierarchyCycle(String, String),
+}
+
+impl std::error::Error for LostLostLandsFaultTransform {}
+
+impl std::fmt::Display for LostLostLandsFaultTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LostLostLandsFaultTransform::HierarchyCycle(child, parent) => {
+                write!(
+                    f,
+                    "Transform {} can not be parented to {} which is itself or one of its children",
+                    child, parent
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use super::*;
+
+    #[test]
+    fn module_faults_convert_and_keep_their_source() {
+        let fault: LostLandsFault =
+            LostLostLandsFaultRealm::ForgedObjectNotFound("Hall".to_string()).into();
+        assert!(matches!(fault, LostLandsFault::Realm(..)));
+        assert_eq!(fault.to_string(), "Forged object Hall not found");
+        assert_eq!(fault.source().unwrap().to_string(), fault.to_string());
+    }
+
+    #[test]
+    fn faults_name_what_went_wrong() {
+        let fault = LostLostLandsFaultForgedObject::TraitRequiredBy(
+            "Fuel".to_string(),
+            "Engine".to_string(),
+        );
+        assert_eq!(fault.to_string(), "Trait Fuel is required by Engine");
+        let fault = LostLostLandsFaultArcaneWeft::DependencyCycle(vec![
+            "egg".to_string(),
+            "hen".to_string(),
+        ]);
+        assert_eq!(
+            fault.to_string(),
+            "Arcane wefts egg, hen depend on each other"
+        );
+    }
+}
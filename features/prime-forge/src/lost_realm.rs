@@ -1,28 +1,100 @@
-use std::{cell::RefCell, rc::Rc, time::Duration};
+use std::{
+    cell::{Ref, RefCell},
+    future::Future,
+    rc::Rc,
+    time::Duration,
+};
 
-use crate::{arcane_weft::ArcaneWeft, forged_object::TraitBundle, forged_trait::ForgedTrait};
+use crate::{
+    arcane_weft::{weave_order, ArcaneWeft, ArcaneWeftEntry, ArcaneWeftInfo},
+    divination::{Divination, DivinationClaims, DivinationFetch},
+    forged_object::TraitBundle,
+    forged_trait::ForgedTrait,
+    lost_lands_fault::{
+        LostLandsFault, LostLostLandsFaultArcaneWeft, LostLostLandsFaultDestinyRift,
+        LostLostLandsFaultRealm,
+    },
+    omen::{foretell, OmenCategory, OmenLevel},
+    realm_vision::RealmVision,
+};
 
 use super::{
-    destiny_rift::{DestinyRift, DestinyRiftManager},
+    destiny_rift::{
+        DestinyRift, DestinyRiftManager, EventReader, RealmSender, RiftDelay, RiftObserverHandle,
+        RiftPhase, RiftPropagation, RiftRead, RiftRetention, ScheduledRiftHandle, SentRift,
+    },
     eonforge::EonForge,
     forged_object::ForgedObject,
-    soul_thread::{SoulThread, SoulThreadManager},
+    soul_thread::{SoulThread, SoulThreadHandle, SoulThreadManager},
 };
 
+#[cfg(feature = "inspector")]
+use crate::arcane_eye::{self, ArcaneEye};
+#[cfg(feature = "hot_reload")]
+use crate::arcane_weft_library::ArcaneWeftLibrary;
+#[cfg(feature = "profiling")]
+use crate::eon_ledger::{EonLedger, LedgerCategory, LedgerStats};
+#[cfg(feature = "scripting")]
+use crate::{destiny_rift::downcast_rift, incantation::ScriptRift};
+#[cfg(feature = "hot_reload")]
+use std::collections::HashSet;
+
 pub struct LostRealm {
     forged_objects: Rc<RefCell<Vec<ForgedObject>>>,
+    divination_claims: RefCell<DivinationClaims>,
     pub eonforge: Rc<RefCell<EonForge>>,
     destiny_rift_manager: Rc<RefCell<DestinyRiftManager>>,
+    // events added while a read guard holds the destiny rift manager
+    pending_destiny_rifts: RefCell<Vec<Box<dyn DestinyRift>>>,
     soul_threads_manager: Rc<RefCell<SoulThreadManager>>,
+    #[cfg(feature = "profiling")]
+    pub eon_ledger: Rc<RefCell<EonLedger>>,
+    #[cfg(feature = "inspector")]
+    arcane_eye: RefCell<Option<ArcaneEye>>,
+    arcane_wefts: Vec<ArcaneWeftEntry>,
+    pending_arcane_wefts: Vec<Box<dyn ArcaneWeft>>,
+    #[cfg(feature = "hot_reload")]
+    arcane_weft_libraries: Vec<ArcaneWeftLibrary>,
+    rift_sender: std::sync::mpsc::Sender<SentRift>,
+    rift_receiver: std::sync::mpsc::Receiver<SentRift>,
+    paused: bool,
+    pending_steps: u32,
 }
 
 impl LostRealm {
     pub fn new() -> LostRealm {
+        #[cfg(feature = "profiling")]
+        let eon_ledger = Rc::new(RefCell::new(EonLedger::new()));
+        let soul_threads_manager = Rc::new(RefCell::new(SoulThreadManager::new()));
+        #[cfg(feature = "profiling")]
+        soul_threads_manager
+            .borrow_mut()
+            .set_eon_ledger(eon_ledger.clone());
+        let (rift_sender, rift_receiver) = std::sync::mpsc::channel();
+        let destiny_rift_manager = Rc::new(RefCell::new(DestinyRiftManager::new()));
+        soul_threads_manager
+            .borrow_mut()
+            .set_destiny_rift_manager(destiny_rift_manager.clone());
+
         LostRealm {
             forged_objects: Rc::new(RefCell::new(Vec::new())),
+            divination_claims: RefCell::new(DivinationClaims::default()),
             eonforge: Rc::new(RefCell::new(EonForge::new())),
-            destiny_rift_manager: Rc::new(RefCell::new(DestinyRiftManager::new())),
-            soul_threads_manager: Rc::new(RefCell::new(SoulThreadManager::new())),
+            destiny_rift_manager,
+            pending_destiny_rifts: RefCell::new(Vec::new()),
+            soul_threads_manager,
+            #[cfg(feature = "profiling")]
+            eon_ledger,
+            #[cfg(feature = "inspector")]
+            arcane_eye: RefCell::new(None),
+            arcane_wefts: Vec::new(),
+            pending_arcane_wefts: Vec::new(),
+            #[cfg(feature = "hot_reload")]
+            arcane_weft_libraries: Vec::new(),
+            rift_sender,
+            rift_receiver,
+            paused: false,
+            pending_steps: 0,
         }
     }
 
@@ -43,15 +115,35 @@ impl LostRealm {
     }
 
     pub fn update(&mut self) {
+        #[cfg(feature = "inspector")]
+        self.gaze_arcane_eye();
+        if self.paused {
+            if self.pending_steps == 0 {
+                return;
+            }
+            self.pending_steps -= 1;
+        }
+
         let dt = self.eonforge.borrow().get_delta_time();
+        self.deliver_pending_destiny_rifts();
+        self.receive_sent_destiny_rifts();
+        self.deliver_scheduled_destiny_rifts(dt);
         let forged_objects = self.forged_objects.clone();
         for object in forged_objects.borrow_mut().iter() {
             object.update(self, dt);
         }
-        self.destiny_rift_manager.borrow_mut().remove_event();
-        self.soul_threads_manager
-            .borrow_mut()
-            .update(self.eonforge.borrow().get_delta_time());
+        #[cfg(feature = "profiling")]
+        let started = std::time::Instant::now();
+        self.flush_destiny_rifts();
+        #[cfg(feature = "profiling")]
+        self.eon_ledger.borrow_mut().record(
+            LedgerCategory::DestinyRiftFlush,
+            "flush_destiny_rifts",
+            started,
+        );
+        let unscaled_dt = self.eonforge.borrow().get_unscaled_delta_time();
+        let soul_threads_manager = self.soul_threads_manager.clone();
+        SoulThreadManager::update_in_realm(&soul_threads_manager, self, dt, unscaled_dt);
     }
 
     pub fn debug_update(&mut self) {
@@ -65,66 +157,931 @@ impl LostRealm {
         }
     }
 
+    /// Skips updates until `resume` is called
+    pub fn pause(&mut self) {
+        self.paused = true;
+        foretell(OmenLevel::Info, OmenCategory::Realm, || {
+            "Realm paused".to_string()
+        });
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.pending_steps = 0;
+        foretell(OmenLevel::Info, OmenCategory::Realm, || {
+            "Realm resumed".to_string()
+        });
+    }
+
+    /// Runs the next `frames` updates while paused
+    pub fn step(&mut self, frames: u32) {
+        self.pending_steps = self.pending_steps.saturating_add(frames);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
     //// Soul thread functions
     /// alias for adding a coroutine
-    pub fn add_soul_thread(&self, thread: SoulThread) {
-        self.soul_threads_manager.borrow_mut().add_thread(thread);
+    pub fn add_soul_thread(&self, thread: SoulThread) -> SoulThreadHandle {
+        self.soul_threads_manager.borrow_mut().add_thread(thread)
+    }
+
+    /// Runs an `async` block as a soul thread, see `SoulThread::from_future`
+    pub fn spawn_soul_thread(
+        &self,
+        name: &str,
+        future: impl Future<Output = ()> + 'static,
+    ) -> SoulThreadHandle {
+        self.add_soul_thread(SoulThread::from_future(name, future))
+    }
+
+    /// Adds a soul thread owned by the forged object, stopped when the object is destroyed
+    /// and paused while it is inactive
+    pub fn start_soul_thread_on(
+        &self,
+        forged_object: &ForgedObject,
+        mut thread: SoulThread,
+    ) -> SoulThreadHandle {
+        thread.set_owner(forged_object);
+        self.add_soul_thread(thread)
+    }
+
+    /// Runs an `async` block as a soul thread owned by the forged object
+    pub fn spawn_soul_thread_on(
+        &self,
+        forged_object: &ForgedObject,
+        name: &str,
+        future: impl Future<Output = ()> + 'static,
+    ) -> SoulThreadHandle {
+        self.start_soul_thread_on(forged_object, SoulThread::from_future(name, future))
+    }
+
+    pub fn get_soul_thread_handles_on(
+        &self,
+        forged_object: &ForgedObject,
+    ) -> Vec<SoulThreadHandle> {
+        self.soul_threads_manager
+            .borrow()
+            .get_soul_thread_handles_on(forged_object.id)
+    }
+
+    /// Soul threads not finished yet with their state, for debugging
+    pub fn get_soul_thread_handles(&self) -> Vec<SoulThreadHandle> {
+        self.soul_threads_manager
+            .borrow()
+            .get_soul_thread_handles()
+            .to_vec()
     }
 
     pub fn stop_all_soul_threads(&self) {
         self.soul_threads_manager.borrow_mut().stop_all();
     }
 
-    pub fn stop_soul_thread_by_name(&self, name: &str) {
-        self.soul_threads_manager.borrow_mut().stop_by_name(name);
+    pub fn stop_soul_thread_by_name(&self, name: &str) -> Result<(), LostLandsFault> {
+        Ok(self.soul_threads_manager.borrow_mut().stop_by_name(name)?)
     }
 
     //// Destiny rift functions
-    /// alias for adding event
+    /// alias for adding event.
+    /// Events added while a `RiftRead`, `peek` or `consume` guard is alive wait until the guard is dropped
     pub fn add_destiny_rift_event(&self, event: impl DestinyRift + 'static) {
-        self.destiny_rift_manager
-            .borrow_mut()
-            .add_event(Box::new(event));
+        self.add_boxed_destiny_rift_event(Box::new(event));
     }
 
-    pub fn consume_destiny_rift_event<T: 'static + DestinyRift>(&self) -> Option<&T> {
-        let mut borrow_mut = self.destiny_rift_manager.borrow_mut();
-        let rc = borrow_mut.consume_event();
-        if let Some(rc) = rc {
-            let rc = unsafe {
-                let ptr = rc as *const T;
+    fn add_boxed_destiny_rift_event(&self, event: Box<dyn DestinyRift>) {
+        #[cfg(feature = "inspector")]
+        if let Some(arcane_eye) = self.arcane_eye.borrow_mut().as_mut() {
+            arcane_eye.publish_rift(event.rift_name(), &event.runic_fields());
+        }
+        self.pending_destiny_rifts.borrow_mut().push(event);
+        self.deliver_pending_destiny_rifts();
+    }
+
+    // hands the pending events to the destiny rift manager, unless a read guard still holds it
+    fn deliver_pending_destiny_rifts(&self) {
+        let Ok(mut destiny_rift_manager) = self.destiny_rift_manager.try_borrow_mut() else {
+            return;
+        };
+        for event in self.pending_destiny_rifts.take() {
+            destiny_rift_manager.add_event(event);
+        }
+    }
+
+    /// Hands the event right away to the traits of `target` and of its transform parents:
+    /// `on_rift_capture` from the root down to the parent of the target, then `on_rift` from the target up to the root.
+    /// Returns the propagation to tell whether a trait handled or stopped it.
+    /// Traits busy running, like the one bubbling from its update, are skipped.
+    pub fn bubble_destiny_rift_event(
+        &mut self,
+        target: uuid::Uuid,
+        event: impl DestinyRift + 'static,
+    ) -> Result<RiftPropagation, LostLandsFault> {
+        self.bubble_destiny_rift(target, Box::new(event), None)
+    }
+
+    /// Bubbles like `bubble_destiny_rift_event`, `origin` gets the event too when the path reaches its forged object,
+    /// e.g. `lost_realm.bubble_destiny_rift_event_from(self, target, Click(1))` from a trait update
+    pub fn bubble_destiny_rift_event_from(
+        &mut self,
+        origin: &mut dyn ForgedTrait,
+        target: uuid::Uuid,
+        event: impl DestinyRift + 'static,
+    ) -> Result<RiftPropagation, LostLandsFault> {
+        self.bubble_destiny_rift(target, Box::new(event), Some(origin))
+    }
+
+    fn bubble_destiny_rift<'o>(
+        &mut self,
+        target: uuid::Uuid,
+        event: Box<dyn DestinyRift>,
+        mut origin: Option<&mut (dyn ForgedTrait + 'o)>,
+    ) -> Result<RiftPropagation, LostLandsFault> {
+        let forged_object = self
+            .get_forged_object_by_id(target)
+            .ok_or_else(|| LostLostLandsFaultRealm::ForgedObjectNotFound(target.to_string()))?;
+        foretell(OmenLevel::Trace, OmenCategory::DestinyRift, || {
+            format!("Bubbling {} from {}", event.rift_name(), forged_object.name)
+        });
+        // forged objects of the transform parents, from the parent of the target to the root
+        let mut ancestors = Vec::new();
+        let mut transform = forged_object.transform.borrow().get_parent();
+        while let Some(parent) = transform {
+            let parent_id = parent.borrow().get_id();
+            // not borrowed, bubbling may start from a trait update
+            let forged_objects = unsafe { &*self.forged_objects.as_ptr() };
+            if let Some(ancestor) = forged_objects
+                .iter()
+                .find(|object| object.transform.borrow().get_id() == parent_id)
+            {
+                ancestors.push(ancestor.id);
+            }
+            transform = parent.borrow().get_parent();
+        }
+
+        let mut propagation = RiftPropagation::new(event, target);
+        let path = ancestors
+            .iter()
+            .rev()
+            .map(|id| (RiftPhase::Capture, *id))
+            .chain(std::iter::once((RiftPhase::Target, target)))
+            .chain(ancestors.iter().map(|id| (RiftPhase::Bubble, *id)));
+        for (phase, id) in path {
+            // objects destroyed by an earlier handler are skipped
+            let Some(forged_object) = self.get_forged_object_by_id(id) else {
+                continue;
+            };
+            let forged_object = unsafe {
+                let ptr = forged_object as *const ForgedObject;
                 &*ptr
             };
-            Some(rc)
+            propagation.enter(phase, id);
+            forged_object.propagate_rift(&mut propagation, self, origin.as_deref_mut());
+            if propagation.is_stopped() {
+                break;
+            }
+        }
+        Ok(propagation)
+    }
+
+    /// Sets how long events of type `T` stay queued, `RiftRetention::Frames(2)` by default.
+    /// Events dropped before being read, peeked or observed are reported as warnings.
+    pub fn set_destiny_rift_retention<T: 'static + DestinyRift>(&self, retention: RiftRetention) {
+        self.destiny_rift_manager
+            .borrow_mut()
+            .set_retention(std::any::TypeId::of::<T>(), retention);
+    }
+
+    pub fn get_destiny_rift_retention<T: 'static + DestinyRift>(&self) -> RiftRetention {
+        self.destiny_rift_manager
+            .borrow()
+            .get_retention(std::any::TypeId::of::<T>())
+    }
+
+    /// Adds the event once `seconds` of scaled time went by, time does not run while the realm is paused.
+    /// NaN or negative `seconds` add it on the next update
+    pub fn add_destiny_rift_event_after(
+        &self,
+        event: impl DestinyRift + 'static,
+        seconds: f32,
+    ) -> ScheduledRiftHandle {
+        self.destiny_rift_manager
+            .borrow_mut()
+            .schedule_event(Box::new(event), RiftDelay::Seconds(seconds))
+    }
+
+    /// Adds the event at the start of the `frames`th next update, 0 counts as 1
+    pub fn add_destiny_rift_event_after_frames(
+        &self,
+        event: impl DestinyRift + 'static,
+        frames: u32,
+    ) -> ScheduledRiftHandle {
+        self.destiny_rift_manager
+            .borrow_mut()
+            .schedule_event(Box::new(event), RiftDelay::Frames(frames))
+    }
+
+    /// Drops a scheduled event that was not added yet
+    pub fn cancel_scheduled_destiny_rift_event(
+        &self,
+        handle: ScheduledRiftHandle,
+    ) -> Result<(), LostLandsFault> {
+        self.destiny_rift_manager
+            .borrow_mut()
+            .cancel_scheduled_event(handle)?;
+        Ok(())
+    }
+
+    pub fn is_destiny_rift_event_scheduled(&self, handle: ScheduledRiftHandle) -> bool {
+        self.destiny_rift_manager
+            .borrow()
+            .is_event_scheduled(handle)
+    }
+
+    pub fn count_scheduled_destiny_rift_events(&self) -> usize {
+        self.destiny_rift_manager.borrow().count_scheduled_events()
+    }
+
+    /// A `Send` handle to add events from other threads
+    pub fn get_realm_sender(&self) -> RealmSender {
+        RealmSender::new(self.rift_sender.clone())
+    }
+
+    fn receive_sent_destiny_rifts(&self) {
+        let mut received = 0;
+        while let Ok(event) = self.rift_receiver.try_recv() {
+            self.add_boxed_destiny_rift_event(event);
+            received += 1;
+        }
+        if received > 0 {
+            foretell(OmenLevel::Trace, OmenCategory::DestinyRift, || {
+                format!("Received {} destiny rifts from realm senders", received)
+            });
+        }
+    }
+
+    fn deliver_scheduled_destiny_rifts(&self, delta_time: f32) {
+        let due = self
+            .destiny_rift_manager
+            .borrow_mut()
+            .tick_scheduled_events(delta_time);
+        for event in due {
+            self.add_boxed_destiny_rift_event(event);
+        }
+    }
+
+    /// Delivers the event to the inbox of one forged object instead of the global queue,
+    /// it stays there until one of its traits takes it or the object is destroyed
+    pub fn send_destiny_rift_event(
+        &self,
+        target: uuid::Uuid,
+        event: impl DestinyRift + 'static,
+    ) -> Result<(), LostLandsFault> {
+        let forged_object = self
+            .get_forged_object_by_id(target)
+            .ok_or_else(|| LostLostLandsFaultRealm::ForgedObjectNotFound(target.to_string()))?;
+        #[cfg(feature = "inspector")]
+        if let Some(arcane_eye) = self.arcane_eye.borrow_mut().as_mut() {
+            arcane_eye.publish_rift(event.rift_name(), &event.runic_fields());
+        }
+        foretell(OmenLevel::Trace, OmenCategory::DestinyRift, || {
+            format!("Sent {} to {}", event.rift_name(), forged_object.name)
+        });
+        forged_object.inbox.borrow_mut().push(Box::new(event));
+        Ok(())
+    }
+
+    /// Takes the last event of type `T`, it stays readable until the next update.
+    /// The guard borrows the destiny rift manager, drop it before taking events
+    pub fn consume_destiny_rift_event<T: 'static + DestinyRift>(
+        &self,
+    ) -> Result<Ref<'_, T>, LostLandsFault> {
+        self.deliver_pending_destiny_rifts();
+        self.destiny_rift_manager
+            .borrow_mut()
+            .consume_event::<T>()?;
+        Ref::filter_map(self.destiny_rift_manager.borrow(), |manager| {
+            manager.get_last_consumed_event::<T>()
+        })
+        .map_err(|_| {
+            LostLostLandsFaultDestinyRift::RiftNotFound(std::any::type_name::<T>().to_string())
+                .into()
+        })
+    }
+
+    /// Takes the oldest event of type `T`
+    pub fn take_destiny_rift_event<T: 'static + DestinyRift>(&self) -> Option<T> {
+        self.deliver_pending_destiny_rifts();
+        self.destiny_rift_manager.borrow_mut().take_event()
+    }
+
+    /// Takes every event of type `T`, from the oldest to the newest
+    pub fn drain_destiny_rift_events<T: 'static + DestinyRift>(
+        &self,
+    ) -> impl Iterator<Item = T> + use<T> {
+        self.deliver_pending_destiny_rifts();
+        self.destiny_rift_manager.borrow_mut().drain_events()
+    }
+
+    /// The event `take_destiny_rift_event` would return, without taking it.
+    /// The guard borrows the destiny rift manager, drop it before taking events
+    pub fn peek_destiny_rift_event<T: 'static + DestinyRift>(&self) -> Option<Ref<'_, T>> {
+        self.deliver_pending_destiny_rifts();
+        Ref::filter_map(self.destiny_rift_manager.borrow(), |manager| {
+            manager.peek_event::<T>()
+        })
+        .ok()
+    }
+
+    pub fn count_destiny_rift_events<T: 'static + DestinyRift>(&self) -> usize {
+        self.deliver_pending_destiny_rifts();
+        self.destiny_rift_manager.borrow().count_events::<T>()
+    }
+
+    pub fn has_destiny_rift_event<T: 'static + DestinyRift>(&self) -> bool {
+        self.deliver_pending_destiny_rifts();
+        self.destiny_rift_manager.borrow().has_events::<T>()
+    }
+
+    /// Events the reader has not seen yet, they stay readable as long as their `RiftRetention` allows
+    pub fn read_destiny_rift_events<T: 'static + DestinyRift>(
+        &self,
+        reader: &mut EventReader<T>,
+    ) -> RiftRead<'_, T> {
+        self.deliver_pending_destiny_rifts();
+        let manager = self.destiny_rift_manager.borrow();
+        let from = manager.advance_reader(reader);
+        RiftRead::new(manager, from)
+    }
+
+    pub fn count_unread_destiny_rift_events<T: 'static + DestinyRift>(
+        &self,
+        reader: &EventReader<T>,
+    ) -> usize {
+        self.deliver_pending_destiny_rifts();
+        self.destiny_rift_manager
+            .borrow()
+            .count_unread_events(reader)
+    }
+
+    /// Calls `observer` with every event of type `T` when events are flushed at the end of `update`.
+    /// Events added by observers are observed on the next flush.
+    pub fn observe<T: 'static + DestinyRift>(
+        &self,
+        mut observer: impl FnMut(&T, &mut LostRealm) + 'static,
+    ) -> RiftObserverHandle {
+        let handle = self.destiny_rift_manager.borrow_mut().add_observer(
+            std::any::TypeId::of::<T>(),
+            None,
+            Box::new(move |event, lost_realm| {
+                if let Some(event) = event.as_any().downcast_ref::<T>() {
+                    observer(event, lost_realm);
+                }
+                true
+            }),
+        );
+        foretell(OmenLevel::Debug, OmenCategory::DestinyRift, || {
+            format!(
+                "Observer {} added for {}",
+                handle,
+                std::any::type_name::<T>()
+            )
+        });
+        handle
+    }
+
+    /// Like `observe` but the observer also gets the forged object, and is dropped with it
+    pub fn observe_forged_object<T: 'static + DestinyRift>(
+        &self,
+        forged_object: &ForgedObject,
+        mut observer: impl FnMut(&T, &mut ForgedObject, &mut LostRealm) + 'static,
+    ) -> RiftObserverHandle {
+        let id = forged_object.id;
+        let handle = self.destiny_rift_manager.borrow_mut().add_observer(
+            std::any::TypeId::of::<T>(),
+            Some(id),
+            Box::new(move |event, lost_realm| {
+                let Some(forged_object) = lost_realm.get_mut_forged_object_by_id(id) else {
+                    return false;
+                };
+                let forged_object = unsafe {
+                    let ptr = forged_object as *mut ForgedObject;
+                    &mut *ptr
+                };
+                if let Some(event) = event.as_any().downcast_ref::<T>() {
+                    observer(event, forged_object, lost_realm);
+                }
+                true
+            }),
+        );
+        foretell(OmenLevel::Debug, OmenCategory::DestinyRift, || {
+            format!(
+                "Observer {} added for {} on {}",
+                handle,
+                std::any::type_name::<T>(),
+                forged_object.name
+            )
+        });
+        handle
+    }
+
+    pub fn unobserve(&self, handle: RiftObserverHandle) -> Result<(), LostLandsFault> {
+        self.destiny_rift_manager
+            .borrow_mut()
+            .remove_observer(handle)?;
+        foretell(OmenLevel::Debug, OmenCategory::DestinyRift, || {
+            format!("Observer {} removed", handle)
+        });
+        Ok(())
+    }
+
+    // ages the events, hands the events of the last update to their observers, then drops expired ones
+    fn flush_destiny_rifts(&mut self) {
+        self.deliver_pending_destiny_rifts();
+        let destiny_rift_manager = self.destiny_rift_manager.clone();
+        let mut dispatch = {
+            let mut destiny_rift_manager = destiny_rift_manager.borrow_mut();
+            destiny_rift_manager.advance_events();
+            destiny_rift_manager.begin_dispatch()
+        };
+        let dropped = if dispatch.is_empty() {
+            Vec::new()
         } else {
-            None
+            dispatch.run(self, &destiny_rift_manager)
+        };
+        for handle in dropped.iter() {
+            foretell(OmenLevel::Debug, OmenCategory::DestinyRift, || {
+                format!("Observer {} dropped with its forged object", handle)
+            });
         }
+        let mut destiny_rift_manager = destiny_rift_manager.borrow_mut();
+        destiny_rift_manager.end_dispatch(dispatch, &dropped);
+        // observers see the events even when their retention ends on this flush
+        destiny_rift_manager.expire_events();
+    }
+
+    // takes the oldest script rift with the given name
+    #[cfg(feature = "scripting")]
+    pub(crate) fn take_script_rift(&self, name: &str) -> Option<ScriptRift> {
+        self.deliver_pending_destiny_rifts();
+        let mut destiny_rift_manager = self.destiny_rift_manager.borrow_mut();
+        let event = destiny_rift_manager
+            .events
+            .get_mut(&std::any::TypeId::of::<ScriptRift>())?
+            .remove_first(|event| {
+                event
+                    .as_any()
+                    .downcast_ref::<ScriptRift>()
+                    .is_some_and(|rift| rift.name == name)
+            })?;
+        downcast_rift::<ScriptRift>(event).ok().map(|event| *event)
     }
 
     /// Arcane Weft functions
     /// alias for plugging
-    pub fn arcane_weft_craft(&mut self, arcane_weft: impl ArcaneWeft) {
-        arcane_weft.craft(self);
+    /// Adds the weft and crafts it with every queued weft, see `craft_arcane_wefts`
+    pub fn arcane_weft_craft<A: ArcaneWeft>(
+        &mut self,
+        arcane_weft: A,
+    ) -> Result<(), LostLostLandsFaultArcaneWeft> {
+        self.add_arcane_weft(arcane_weft)?;
+        self.craft_arcane_wefts()
+    }
+
+    /// Queues a weft until `craft_arcane_wefts` is called
+    pub fn add_arcane_weft<A: ArcaneWeft>(
+        &mut self,
+        arcane_weft: A,
+    ) -> Result<(), LostLostLandsFaultArcaneWeft> {
+        let name = arcane_weft.name();
+        let queued = self
+            .pending_arcane_wefts
+            .iter()
+            .any(|pending| pending.name() == name);
+        if queued || self.has_arcane_weft(name) {
+            return Err(LostLostLandsFaultArcaneWeft::DuplicateArcaneWeft(
+                name.to_string(),
+            ));
+        }
+        self.pending_arcane_wefts.push(Box::new(arcane_weft));
+        Ok(())
+    }
+
+    /// Crafts the queued wefts after their dependencies, then finishes them in the same order.
+    /// Wefts added while crafting are crafted next.
+    /// When a dependency is missing or cyclic nothing is crafted and the wefts stay queued.
+    pub fn craft_arcane_wefts(&mut self) -> Result<(), LostLostLandsFaultArcaneWeft> {
+        while !self.pending_arcane_wefts.is_empty() {
+            let batch = self
+                .pending_arcane_wefts
+                .iter()
+                .map(|arcane_weft| ArcaneWeftInfo::of(arcane_weft.as_ref()))
+                .collect::<Vec<_>>();
+            let order = weave_order(&self.arcane_wefts, &batch)?;
+            let mut pending = std::mem::take(&mut self.pending_arcane_wefts)
+                .into_iter()
+                .map(Some)
+                .collect::<Vec<_>>();
+
+            for index in order.iter() {
+                let Some(mut arcane_weft) = pending[*index].take() else {
+                    continue;
+                };
+                #[cfg(feature = "profiling")]
+                let started = std::time::Instant::now();
+                arcane_weft.craft(self);
+                #[cfg(feature = "profiling")]
+                self.eon_ledger.borrow_mut().record(
+                    LedgerCategory::ArcaneWeftCraft,
+                    arcane_weft.name(),
+                    started,
+                );
+                foretell(OmenLevel::Info, OmenCategory::ArcaneWeft, || {
+                    format!(
+                        "Arcane weft {} {} crafted",
+                        batch[*index].get_name(),
+                        batch[*index].get_version()
+                    )
+                });
+                self.arcane_wefts.push(ArcaneWeftEntry {
+                    info: batch[*index].clone(),
+                    arcane_weft: Some(arcane_weft),
+                    #[cfg(feature = "hot_reload")]
+                    library: None,
+                });
+            }
+
+            for index in order {
+                let name = batch[index].get_name();
+                let Some(entry) = self
+                    .arcane_wefts
+                    .iter_mut()
+                    .find(|entry| entry.info.get_name() == name)
+                else {
+                    continue;
+                };
+                let Some(mut arcane_weft) = entry.arcane_weft.take() else {
+                    continue;
+                };
+                arcane_weft.finish(self);
+                if let Some(entry) = self
+                    .arcane_wefts
+                    .iter_mut()
+                    .find(|entry| entry.info.get_name() == name)
+                {
+                    entry.arcane_weft = Some(arcane_weft);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Cleans up a crafted weft and forgets it, library wefts unload their library
+    pub fn remove_arcane_weft(&mut self, name: &str) -> Result<(), LostLostLandsFaultArcaneWeft> {
+        let index = self
+            .arcane_wefts
+            .iter()
+            .position(|entry| entry.info.get_name() == name)
+            .ok_or_else(|| LostLostLandsFaultArcaneWeft::ArcaneWeftNotFound(name.to_string()))?;
+        self.ensure_arcane_weft_unused(name)?;
+        #[cfg(feature = "hot_reload")]
+        if let Some(path) = self.arcane_wefts[index].library.clone() {
+            return self.unload_arcane_weft_library(path);
+        }
+        let mut entry = self.arcane_wefts.remove(index);
+        if let Some(arcane_weft) = entry.arcane_weft.as_mut() {
+            arcane_weft.cleanup(self);
+        }
+        foretell(OmenLevel::Info, OmenCategory::ArcaneWeft, || {
+            format!("Arcane weft {} removed", name)
+        });
+        Ok(())
+    }
+
+    pub fn has_arcane_weft(&self, name: &str) -> bool {
+        self.get_arcane_weft(name).is_some()
+    }
+
+    pub fn get_arcane_weft(&self, name: &str) -> Option<&ArcaneWeftInfo> {
+        self.arcane_wefts
+            .iter()
+            .map(|entry| &entry.info)
+            .find(|info| info.get_name() == name)
+    }
+
+    /// Crafted wefts in the order they were crafted
+    pub fn get_arcane_wefts(&self) -> Vec<&ArcaneWeftInfo> {
+        self.arcane_wefts.iter().map(|entry| &entry.info).collect()
+    }
+
+    pub fn get_arcane_weft_by_type<A: ArcaneWeft>(&self) -> Option<&A> {
+        self.arcane_wefts.iter().find_map(|entry| {
+            let arcane_weft: &dyn std::any::Any = entry.arcane_weft.as_deref()?;
+            arcane_weft.downcast_ref::<A>()
+        })
+    }
+
+    fn ensure_arcane_weft_unused(&self, name: &str) -> Result<(), LostLostLandsFaultArcaneWeft> {
+        match self
+            .arcane_wefts
+            .iter()
+            .find(|entry| entry.info.depends_on(name))
+        {
+            Some(dependent) => Err(LostLostLandsFaultArcaneWeft::ArcaneWeftRequiredBy(
+                name.to_string(),
+                dependent.info.get_name().to_string(),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    // runs the cleanup of every crafted weft, the last crafted first
+    fn cleanup_arcane_wefts(&mut self) {
+        while let Some(mut entry) = self.arcane_wefts.pop() {
+            if let Some(arcane_weft) = entry.arcane_weft.as_mut() {
+                arcane_weft.cleanup(self);
+            }
+            #[cfg(feature = "hot_reload")]
+            if let Some(path) = entry.library {
+                self.cleanup_arcane_weft_library(&path);
+            }
+        }
+    }
+
+    /// Loads a `cdylib` exporting `declare_arcane_weft!`, then crafts and finishes its weft.
+    /// Its dependencies must already be crafted.
+    /// Must be called between updates.
+    #[cfg(feature = "hot_reload")]
+    pub fn load_arcane_weft_library(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), LostLostLandsFaultArcaneWeft> {
+        let path = path.as_ref();
+        if self.find_arcane_weft_library(path).is_some() {
+            return Err(LostLostLandsFaultArcaneWeft::LibraryAlreadyLoaded(
+                path.display().to_string(),
+            ));
+        }
+        let mut library = ArcaneWeftLibrary::open(path)?;
+        let Some(info) = library.arcane_weft.as_deref().map(ArcaneWeftInfo::of) else {
+            return Err(LostLostLandsFaultArcaneWeft::LibraryLoad(
+                path.display().to_string(),
+                "no arcane weft".to_string(),
+            ));
+        };
+        let queued = self
+            .pending_arcane_wefts
+            .iter()
+            .any(|pending| pending.name() == info.get_name());
+        if queued || self.has_arcane_weft(info.get_name()) {
+            return Err(LostLostLandsFaultArcaneWeft::DuplicateArcaneWeft(
+                info.get_name().to_string(),
+            ));
+        }
+        weave_order(&self.arcane_wefts, std::slice::from_ref(&info))?;
+
+        let existing = self
+            .forged_objects
+            .borrow()
+            .iter()
+            .map(|object| object.id)
+            .collect::<HashSet<_>>();
+        if let Some(arcane_weft) = library.arcane_weft.as_mut() {
+            #[cfg(feature = "profiling")]
+            let started = std::time::Instant::now();
+            arcane_weft.craft(self);
+            #[cfg(feature = "profiling")]
+            self.eon_ledger.borrow_mut().record(
+                LedgerCategory::ArcaneWeftCraft,
+                info.get_name(),
+                started,
+            );
+            arcane_weft.finish(self);
+        }
+        library.forged_objects = self
+            .forged_objects
+            .borrow()
+            .iter()
+            .map(|object| object.id)
+            .filter(|id| !existing.contains(id))
+            .collect();
+        foretell(OmenLevel::Info, OmenCategory::ArcaneWeft, || {
+            format!(
+                "Arcane weft {} {} loaded from {}",
+                info.get_name(),
+                info.get_version(),
+                path.display()
+            )
+        });
+        self.arcane_wefts.push(ArcaneWeftEntry {
+            info,
+            arcane_weft: None,
+            library: Some(path.to_path_buf()),
+        });
+        self.arcane_weft_libraries.push(library);
+        Ok(())
+    }
+
+    /// Cleans up the library's weft, destroys what it forged and anything else backed by its code,
+    /// then closes it
+    #[cfg(feature = "hot_reload")]
+    pub fn unload_arcane_weft_library(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), LostLostLandsFaultArcaneWeft> {
+        self.close_arcane_weft_library(path.as_ref(), false)
+    }
+
+    /// Unloads and loads the library again, wefts depending on it are kept
+    #[cfg(feature = "hot_reload")]
+    pub fn reload_arcane_weft_library(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), LostLostLandsFaultArcaneWeft> {
+        self.close_arcane_weft_library(path.as_ref(), true)?;
+        self.load_arcane_weft_library(path)
+    }
+
+    /// Reloads every library whose file was rebuilt, returning their paths
+    #[cfg(feature = "hot_reload")]
+    pub fn reload_changed_arcane_weft_libraries(
+        &mut self,
+    ) -> Result<Vec<std::path::PathBuf>, LostLostLandsFaultArcaneWeft> {
+        let changed = self
+            .arcane_weft_libraries
+            .iter()
+            .filter(|library| library.has_changed())
+            .map(|library| library.path().to_path_buf())
+            .collect::<Vec<_>>();
+        for path in changed.iter() {
+            self.reload_arcane_weft_library(path)?;
+        }
+        Ok(changed)
+    }
+
+    #[cfg(feature = "hot_reload")]
+    pub fn get_arcane_weft_libraries(&self) -> &[ArcaneWeftLibrary] {
+        &self.arcane_weft_libraries
+    }
+
+    #[cfg(feature = "hot_reload")]
+    fn find_arcane_weft_library(&self, path: &std::path::Path) -> Option<usize> {
+        self.arcane_weft_libraries
+            .iter()
+            .position(|library| library.path() == path)
+    }
+
+    #[cfg(feature = "hot_reload")]
+    fn close_arcane_weft_library(
+        &mut self,
+        path: &std::path::Path,
+        reloading: bool,
+    ) -> Result<(), LostLostLandsFaultArcaneWeft> {
+        let index = self.find_arcane_weft_library(path).ok_or_else(|| {
+            LostLostLandsFaultArcaneWeft::LibraryNotLoaded(path.display().to_string())
+        })?;
+        let entry = self
+            .arcane_wefts
+            .iter()
+            .position(|entry| entry.library.as_deref() == Some(path));
+        if let Some(entry) = entry {
+            if !reloading {
+                let name = self.arcane_wefts[entry].info.get_name().to_string();
+                self.ensure_arcane_weft_unused(&name)?;
+            }
+            self.arcane_wefts.remove(entry);
+            self.cleanup_arcane_weft_library(path);
+        }
+        let library = self.arcane_weft_libraries.remove(index);
+        self.purge_arcane_weft_library(&library);
+        drop(library);
+        foretell(OmenLevel::Info, OmenCategory::ArcaneWeft, || {
+            format!("Arcane weft library {} unloaded", path.display())
+        });
+        Ok(())
+    }
+
+    #[cfg(feature = "hot_reload")]
+    fn cleanup_arcane_weft_library(&mut self, path: &std::path::Path) {
+        let Some(index) = self.find_arcane_weft_library(path) else {
+            return;
+        };
+        let Some(mut arcane_weft) = self.arcane_weft_libraries[index].arcane_weft.take() else {
+            return;
+        };
+        arcane_weft.cleanup(self);
+        if let Some(index) = self.find_arcane_weft_library(path) {
+            self.arcane_weft_libraries[index].arcane_weft = Some(arcane_weft);
+        }
+    }
+
+    // nothing whose vtable lives in the library may outlive it
+    #[cfg(feature = "hot_reload")]
+    fn purge_arcane_weft_library(&mut self, library: &ArcaneWeftLibrary) {
+        let image = library.image();
+        // wefts the library added are cleaned up like any other removed weft
+        let mut index = self.arcane_wefts.len();
+        while index > 0 {
+            index -= 1;
+            let owned = self.arcane_wefts[index]
+                .arcane_weft
+                .as_deref()
+                .is_some_and(|arcane_weft| image.owns(arcane_weft));
+            if owned {
+                let mut entry = self.arcane_wefts.remove(index);
+                if let Some(arcane_weft) = entry.arcane_weft.as_mut() {
+                    arcane_weft.cleanup(self);
+                }
+                index = index.min(self.arcane_wefts.len());
+            }
+        }
+        self.pending_arcane_wefts
+            .retain(|arcane_weft| !image.owns(arcane_weft.as_ref()));
+
+        let mut forged_objects = self.forged_objects.borrow_mut();
+        forged_objects.retain(|object| !library.forged_objects.contains(&object.id));
+        for object in forged_objects.iter_mut() {
+            object
+                .forged_traits
+                .retain(|forged_trait| !image.owns(&**forged_trait));
+            object.inbox.borrow_mut().retain(|event| !image.owns(event));
+        }
+        // sent events may come from the library too
+        self.receive_sent_destiny_rifts();
+        let mut destiny_rift_manager = self.destiny_rift_manager.borrow_mut();
+        for events in destiny_rift_manager.events.values_mut() {
+            events.retain(|event| !image.owns(event));
+        }
+        destiny_rift_manager
+            .consumed_events
+            .retain(|event| !image.owns(&**event));
+        destiny_rift_manager
+            .observers
+            .retain(|observer| !image.owns(&*observer.callback));
+        destiny_rift_manager.retain_scheduled_events(|event| !image.owns(event));
+        drop(destiny_rift_manager);
+        self.soul_threads_manager
+            .borrow_mut()
+            .retain_soul_threads(|thread| !thread.is_owned_by(image));
+        self.soul_threads_manager
+            .borrow_mut()
+            .retain_finished_callbacks(|callback| !image.owns(callback));
+        // trait names recorded by the ledger point into the library
+        #[cfg(feature = "profiling")]
+        self.eon_ledger.borrow_mut().clear();
     }
 
     /// Forged Object functions
     /// alias for adding object
+    /// fails when the trait bundle can not be crafted
+    #[allow(clippy::mut_from_ref)]
     pub fn forge_new_object_mut(
         &self,
         name: &str,
         traits: impl TraitBundle,
-    ) -> Option<&mut ForgedObject> {
-        let mut forged_object = ForgedObject::new(name.to_string());
-        traits.craft_trait_bundle(&mut forged_object);
-        self.add_object(forged_object);
-        self.get_mut_forged_object(name)
+    ) -> Result<&mut ForgedObject, LostLandsFault> {
+        let id = self.forge_new_object(name, traits)?.id;
+        self.get_mut_forged_object_by_id(id)
+            .ok_or_else(|| LostLostLandsFaultRealm::ForgedObjectNotFound(name.to_string()).into())
     }
 
-    pub fn forge_new_object(&self, name: &str, traits: impl TraitBundle) -> Option<&ForgedObject> {
+    pub fn forge_new_object(
+        &self,
+        name: &str,
+        traits: impl TraitBundle,
+    ) -> Result<&ForgedObject, LostLandsFault> {
+        // a push may move the objects a divination is handing out
+        self.ensure_not_divining(name)?;
         let mut forged_object = ForgedObject::new(name.to_string());
-        traits.craft_trait_bundle(&mut forged_object);
+        traits.craft_trait_bundle(&mut forged_object)?;
+        let id = forged_object.id;
+        foretell(OmenLevel::Debug, OmenCategory::Realm, || {
+            format!("Forged object {} <{}>", name, id)
+        });
         self.add_object(forged_object);
-        self.get_forged_object(name)
+        // looked up by id, names don't have to be unique
+        self.get_forged_object_by_id(id)
+            .ok_or_else(|| LostLostLandsFaultRealm::ForgedObjectNotFound(name.to_string()).into())
+    }
+
+    pub fn get_forged_object_by_id(&self, id: uuid::Uuid) -> Option<&ForgedObject> {
+        let borrow = unsafe {
+            let ptr = self.forged_objects.as_ptr();
+            &*ptr
+        };
+        borrow.iter().find(|object| object.id == id)
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    pub fn get_mut_forged_object_by_id(&self, id: uuid::Uuid) -> Option<&mut ForgedObject> {
+        let mut borrow_mut = self.forged_objects.borrow_mut();
+        let rc = borrow_mut.iter_mut().find(|object| object.id == id)?;
+        let rc = unsafe {
+            let ptr = rc as *mut ForgedObject;
+            &mut *ptr
+        };
+        Some(rc)
     }
 
     pub fn get_forged_object(&self, name: &str) -> Option<&ForgedObject> {
@@ -144,6 +1101,7 @@ impl LostRealm {
         }
     }
 
+    #[allow(clippy::mut_from_ref)]
     pub fn get_mut_forged_object(&self, name: &str) -> Option<&mut ForgedObject> {
         let mut borrow_mut = self.forged_objects.borrow_mut();
         let rc = borrow_mut.iter_mut().find(|object| object.name == name);
@@ -188,6 +1146,7 @@ impl LostRealm {
             None
         }
     }
+    #[allow(clippy::mut_from_ref)]
     pub fn get_mut_trait_by_type<T: 'static + ForgedTrait>(&self) -> Option<&mut T> {
         let mut borrow_mut = self.forged_objects.borrow_mut();
         let rc = borrow_mut
@@ -205,73 +1164,91 @@ impl LostRealm {
         }
     }
 
-    pub fn destroy_forged_object(&self, name: &str) -> Option<ForgedObject> {
+    fn ensure_not_divining(&self, name: &str) -> Result<(), LostLandsFault> {
+        if self.divination_claims.borrow().is_divining() {
+            return Err(LostLostLandsFaultRealm::Divining(name.to_string()).into());
+        }
+        Ok(())
+    }
+
+    pub fn destroy_forged_object(&self, name: &str) -> Result<ForgedObject, LostLandsFault> {
+        self.ensure_not_divining(name)?;
         let index = self
             .forged_objects
             .borrow()
             .iter()
-            .position(|object| object.name == name);
-        index.map(|index| self.forged_objects.borrow_mut().remove(index))
+            .position(|object| object.name == name)
+            .ok_or_else(|| LostLostLandsFaultRealm::ForgedObjectNotFound(name.to_string()))?;
+        foretell(OmenLevel::Debug, OmenCategory::Realm, || {
+            format!("Destroyed forged object {}", name)
+        });
+        let forged_object = self.forged_objects.borrow_mut().remove(index);
+        // undelivered events, observers and owned soul threads die with their target
+        self.bury_forged_object(&forged_object);
+        Ok(forged_object)
     }
 
     pub fn destroy_forged_object_by_ref(
         &self,
         forged_object: &ForgedObject,
-    ) -> Option<ForgedObject> {
+    ) -> Result<ForgedObject, LostLandsFault> {
+        self.ensure_not_divining(&forged_object.name)?;
         let index = self
             .forged_objects
             .borrow()
             .iter()
-            .position(|object| object.id == forged_object.id);
-        index.map(|index| self.forged_objects.borrow_mut().remove(index))
+            .position(|object| object.id == forged_object.id)
+            .ok_or_else(|| {
+                LostLostLandsFaultRealm::ForgedObjectNotFound(forged_object.name.clone())
+            })?;
+        foretell(OmenLevel::Debug, OmenCategory::Realm, || {
+            format!("Destroyed forged object {}", forged_object.name)
+        });
+        let forged_object = self.forged_objects.borrow_mut().remove(index);
+        self.bury_forged_object(&forged_object);
+        Ok(forged_object)
     }
 
-    pub fn get_parent_forged_object(&self, trait_: &impl ForgedTrait) -> Option<&ForgedObject> {
-        let father_id = trait_.get_father();
-        if let Some(id) = father_id {
-            let borrow = unsafe {
-                let ptr = self.forged_objects.as_ptr();
-                &*ptr
-            };
-            let rc = borrow
-                .iter()
-                .find(|object| object.id == uuid::Uuid::parse_str(id.as_str()).unwrap());
-            if let Some(rc) = rc {
-                let rc = unsafe {
-                    let ptr = rc as *const ForgedObject;
-                    &*ptr
-                };
-                Some(rc)
-            } else {
-                None
+    // a read guard holding the destiny rift manager leaves the observers to drop themselves on the next flush
+    fn bury_forged_object(&self, forged_object: &ForgedObject) {
+        forged_object.clear_inbox();
+        if let Ok(mut destiny_rift_manager) = self.destiny_rift_manager.try_borrow_mut() {
+            for handle in destiny_rift_manager.remove_observers_of(forged_object.id) {
+                foretell(OmenLevel::Debug, OmenCategory::DestinyRift, || {
+                    format!("Observer {} dropped with its forged object", handle)
+                });
             }
-        } else {
-            None
         }
+        self.soul_threads_manager
+            .borrow()
+            .stop_owned_by(forged_object.id);
+    }
+
+    pub fn get_parent_forged_object(
+        &self,
+        trait_: &impl ForgedTrait,
+    ) -> Result<&ForgedObject, LostLandsFault> {
+        let id = Self::father_id(trait_)?;
+        self.get_forged_object_by_id(id)
+            .ok_or_else(|| LostLostLandsFaultRealm::ForgedObjectNotFound(id.to_string()).into())
     }
 
+    #[allow(clippy::mut_from_ref)]
     pub fn get_mut_parent_forged_object(
         &self,
         trait_: &impl ForgedTrait,
-    ) -> Option<&mut ForgedObject> {
-        let father_id = trait_.get_father();
-        if let Some(id) = father_id {
-            let mut borrow_mut = self.forged_objects.borrow_mut();
-            let rc = borrow_mut
-                .iter_mut()
-                .find(|object| object.id == uuid::Uuid::parse_str(id.as_str()).unwrap());
-            if let Some(rc) = rc {
-                let rc = unsafe {
-                    let ptr = rc as *const ForgedObject;
-                    &mut *(ptr as *mut ForgedObject)
-                };
-                Some(rc)
-            } else {
-                None
-            }
-        } else {
-            None
-        }
+    ) -> Result<&mut ForgedObject, LostLandsFault> {
+        let id = Self::father_id(trait_)?;
+        self.get_mut_forged_object_by_id(id)
+            .ok_or_else(|| LostLostLandsFaultRealm::ForgedObjectNotFound(id.to_string()).into())
+    }
+
+    fn father_id(trait_: &impl ForgedTrait) -> Result<uuid::Uuid, LostLostLandsFaultRealm> {
+        let father_id = trait_
+            .get_father()
+            .ok_or_else(|| LostLostLandsFaultRealm::OrphanTrait(trait_.trait_name().to_string()))?;
+        uuid::Uuid::parse_str(&father_id)
+            .map_err(|_| LostLostLandsFaultRealm::InvalidForgedObjectId(father_id))
     }
 
     pub fn get_all_forged_objects_by_trait<T: 'static + ForgedTrait>(&self) -> Vec<&ForgedObject> {
@@ -300,6 +1277,7 @@ impl LostRealm {
         rc
     }
 
+    #[allow(clippy::mut_from_ref)]
     pub fn get_mut_all_forged_objects_by_trait<T: 'static + ForgedTrait>(
         &self,
     ) -> Vec<&mut ForgedObject> {
@@ -315,12 +1293,12 @@ impl LostRealm {
         rc
     }
 
+    #[allow(clippy::mut_from_ref)]
     pub fn get_mut_all_trait_by_type<T: 'static + ForgedTrait>(&self) -> Vec<&mut T> {
         let mut borrow_mut = self.forged_objects.borrow_mut();
         let rc = borrow_mut
             .iter_mut()
-            .filter(|object| object.get_trait::<T>().is_ok())
-            .map(|object| object.get_trait_mut::<T>().unwrap())
+            .filter_map(|object| object.get_trait_mut::<T>().ok())
             .map(|trait_| unsafe {
                 let ptr = trait_ as *const T;
                 &mut *(ptr as *mut T)
@@ -329,6 +1307,78 @@ impl LostRealm {
         rc
     }
 
+    /// Divination functions
+    /// alias for querying forged objects by their traits
+    /// panics when a fetched trait type is already divined mutably, or fetched mutably elsewhere
+    pub fn divine<Q: DivinationFetch>(&self) -> Divination<'_, Q> {
+        Divination::new(&self.forged_objects, &self.divination_claims)
+    }
+
+    /// Eon Ledger functions
+    /// alias for profiling
+    #[cfg(feature = "profiling")]
+    pub fn top_slowest_traits(&self, n: usize) -> Vec<(&'static str, LedgerStats)> {
+        self.eon_ledger.borrow().top_slowest_traits(n)
+    }
+
+    #[cfg(feature = "profiling")]
+    pub fn export_chrome_trace(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        self.eon_ledger.borrow().export_chrome_trace(path)
+    }
+
+    /// Realm Vision functions
+    /// alias for dumping the realm state
+    pub fn dump(&self) -> RealmVision {
+        // read like the other accessors, `update` holds the objects while traits run
+        let forged_objects = unsafe {
+            let ptr = self.forged_objects.as_ptr();
+            &*ptr
+        };
+        RealmVision::scry(
+            forged_objects,
+            &self.destiny_rift_manager.borrow(),
+            &self.soul_threads_manager.borrow(),
+            self.get_delta_time(),
+            self.get_time_elapsed(),
+        )
+    }
+
+    /// Arcane Eye functions
+    /// alias for the remote inspector
+    #[cfg(feature = "inspector")]
+    pub fn open_arcane_eye(&self, port: u16) -> std::io::Result<std::net::SocketAddr> {
+        let arcane_eye = ArcaneEye::open(port)?;
+        let address = arcane_eye.address();
+        *self.arcane_eye.borrow_mut() = Some(arcane_eye);
+        Ok(address)
+    }
+
+    #[cfg(feature = "inspector")]
+    pub fn close_arcane_eye(&self) {
+        self.arcane_eye.borrow_mut().take();
+    }
+
+    #[cfg(feature = "inspector")]
+    fn gaze_arcane_eye(&mut self) {
+        let requests = match self.arcane_eye.borrow_mut().as_mut() {
+            Some(arcane_eye) => arcane_eye.gaze(),
+            None => return,
+        };
+        for (client, line) in requests {
+            let response = arcane_eye::answer(self, client, &line);
+            if let Some(arcane_eye) = self.arcane_eye.borrow_mut().as_mut() {
+                arcane_eye.reply(client, &response);
+            }
+        }
+    }
+
+    #[cfg(feature = "inspector")]
+    pub(crate) fn watch_arcane_eye_rifts(&self, client: usize, watching: bool) {
+        if let Some(arcane_eye) = self.arcane_eye.borrow_mut().as_mut() {
+            arcane_eye.watch_rifts(client, watching);
+        }
+    }
+
     // EonForge
     pub fn get_delta_time(&self) -> f32 {
         self.eonforge.borrow().get_delta_time()
@@ -337,6 +1387,24 @@ impl LostRealm {
     pub fn get_time_elapsed(&self) -> Duration {
         self.eonforge.borrow().get_time_elapsed_since_start()
     }
+
+    pub fn get_unscaled_delta_time(&self) -> f32 {
+        self.eonforge.borrow().get_unscaled_delta_time()
+    }
+
+    pub fn set_time_scale(&self, time_scale: f32) {
+        self.eonforge.borrow_mut().set_time_scale(time_scale);
+    }
+
+    pub fn get_time_scale(&self) -> f32 {
+        self.eonforge.borrow().get_time_scale()
+    }
+}
+
+impl Drop for LostRealm {
+    fn drop(&mut self) {
+        self.cleanup_arcane_wefts();
+    }
 }
 
 impl Default for LostRealm {
@@ -344,3 +1412,568 @@ impl Default for LostRealm {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        lost_lands_fault::{
+            LostLostLandsFaultDestinyRift, LostLostLandsFaultForgedObject, LostLostLandsFaultRealm,
+            LostLostLandsFaultSoulThread,
+        },
+        testing::{test_rift, test_trait},
+    };
+
+    struct Lantern {
+        father: Option<String>,
+    }
+    test_trait!(Lantern);
+    impl ForgedTrait for Lantern {}
+
+    fn lantern() -> Lantern {
+        Lantern { father: None }
+    }
+
+    struct Chime(u32);
+    struct Toll;
+    test_rift!(Chime, Toll);
+
+    #[test]
+    fn broken_bundle_forges_nothing() {
+        let lost_realm = LostRealm::new();
+        assert!(matches!(
+            lost_realm.forge_new_object("Hall", (lantern(), lantern())),
+            Err(LostLandsFault::ForgedObject(
+                LostLostLandsFaultForgedObject::TraitAlreadyExists(..)
+            ))
+        ));
+        assert!(lost_realm.get_forged_object("Hall").is_none());
+    }
+
+    #[test]
+    fn missing_objects_and_threads_are_faults() {
+        let lost_realm = LostRealm::new();
+        assert!(matches!(
+            lost_realm.destroy_forged_object("Hall"),
+            Err(LostLandsFault::Realm(
+                LostLostLandsFaultRealm::ForgedObjectNotFound(..)
+            ))
+        ));
+        assert!(matches!(
+            lost_realm.stop_soul_thread_by_name("Vigil"),
+            Err(LostLandsFault::SoulThread(LostLostLandsFaultSoulThread::SoulThreadNotFound(name)))
+                if name == "Vigil"
+        ));
+    }
+
+    #[test]
+    fn parent_of_a_loose_trait_is_a_fault() {
+        let lost_realm = LostRealm::new();
+        assert!(matches!(
+            lost_realm.get_parent_forged_object(&lantern()),
+            Err(LostLandsFault::Realm(LostLostLandsFaultRealm::OrphanTrait(
+                ..
+            )))
+        ));
+        let stray = Lantern {
+            father: Some("not an id".to_string()),
+        };
+        assert!(matches!(
+            lost_realm.get_parent_forged_object(&stray),
+            Err(LostLandsFault::Realm(LostLostLandsFaultRealm::InvalidForgedObjectId(id)))
+                if id == "not an id"
+        ));
+
+        let hall = lost_realm.forge_new_object("Hall", (lantern(),)).unwrap();
+        let held = hall.get_trait::<Lantern>().unwrap();
+        assert_eq!(
+            lost_realm.get_parent_forged_object(held).unwrap().id,
+            hall.id
+        );
+    }
+
+    #[test]
+    fn peeked_event_is_still_taken() {
+        let lost_realm = LostRealm::new();
+        lost_realm.add_destiny_rift_event(Chime(1));
+        lost_realm.add_destiny_rift_event(Chime(2));
+        let peeked = lost_realm
+            .peek_destiny_rift_event::<Chime>()
+            .map(|chime| chime.0);
+        assert_eq!(peeked, Some(1));
+        let taken = lost_realm
+            .take_destiny_rift_event::<Chime>()
+            .map(|chime| chime.0);
+        assert_eq!(taken, Some(1));
+        let peeked = lost_realm
+            .peek_destiny_rift_event::<Chime>()
+            .map(|chime| chime.0);
+        assert_eq!(peeked, Some(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "already borrowed")]
+    fn take_while_peeking_panics_instead_of_freeing_the_event() {
+        let lost_realm = LostRealm::new();
+        lost_realm.add_destiny_rift_event(Chime(1));
+        let peeked = lost_realm.peek_destiny_rift_event::<Chime>().unwrap();
+        let _ = lost_realm.take_destiny_rift_event::<Chime>();
+        assert_eq!(peeked.0, 1);
+    }
+
+    #[test]
+    fn consumed_event_outlives_a_drain() {
+        let lost_realm = LostRealm::new();
+        lost_realm.add_destiny_rift_event(Chime(1));
+        lost_realm.add_destiny_rift_event(Chime(2));
+        let consumed = lost_realm.consume_destiny_rift_event::<Chime>().unwrap().0;
+        assert_eq!(consumed, 2);
+        let drained = lost_realm
+            .drain_destiny_rift_events::<Chime>()
+            .map(|chime| chime.0)
+            .collect::<Vec<_>>();
+        assert_eq!(drained, vec![1]);
+        assert!(lost_realm.consume_destiny_rift_event::<Chime>().is_err());
+    }
+
+    #[test]
+    fn observers_see_each_event_once_on_flush() {
+        let heard = Rc::new(RefCell::new(Vec::new()));
+        let mut lost_realm = LostRealm::new();
+        let handle = lost_realm.observe::<Chime>({
+            let heard = heard.clone();
+            move |chime, _| heard.borrow_mut().push(chime.0)
+        });
+        lost_realm.add_destiny_rift_event(Chime(1));
+        lost_realm.add_destiny_rift_event(Chime(2));
+        assert!(heard.borrow().is_empty());
+        lost_realm.update();
+        lost_realm.update();
+        assert_eq!(*heard.borrow(), vec![1, 2]);
+
+        lost_realm.unobserve(handle).unwrap();
+        lost_realm.add_destiny_rift_event(Chime(3));
+        lost_realm.update();
+        assert_eq!(*heard.borrow(), vec![1, 2]);
+        assert!(matches!(
+            lost_realm.unobserve(handle),
+            Err(LostLandsFault::DestinyRift(
+                LostLostLandsFaultDestinyRift::ObserverNotFound(..)
+            ))
+        ));
+    }
+
+    #[test]
+    fn events_sent_by_observers_are_observed_on_the_next_flush() {
+        let tolls = Rc::new(RefCell::new(0));
+        let mut lost_realm = LostRealm::new();
+        lost_realm.observe::<Chime>(|_, lost_realm| lost_realm.add_destiny_rift_event(Toll));
+        lost_realm.observe::<Toll>({
+            let tolls = tolls.clone();
+            move |_, _| *tolls.borrow_mut() += 1
+        });
+        lost_realm.add_destiny_rift_event(Chime(1));
+        lost_realm.update();
+        assert_eq!(*tolls.borrow(), 0);
+        lost_realm.update();
+        assert_eq!(*tolls.borrow(), 1);
+    }
+
+    #[test]
+    fn object_observers_are_dropped_with_their_object() {
+        let heard = Rc::new(RefCell::new(Vec::new()));
+        let mut lost_realm = LostRealm::new();
+        let bell = lost_realm.forge_new_object("Bell", (lantern(),)).unwrap();
+        let handle = lost_realm.observe_forged_object::<Chime>(bell, {
+            let heard = heard.clone();
+            move |chime, forged_object, _| {
+                heard
+                    .borrow_mut()
+                    .push(format!("{} {}", forged_object.name, chime.0))
+            }
+        });
+        lost_realm.add_destiny_rift_event(Chime(1));
+        lost_realm.update();
+        assert_eq!(*heard.borrow(), vec!["Bell 1"]);
+
+        lost_realm.destroy_forged_object("Bell").unwrap();
+        lost_realm.add_destiny_rift_event(Chime(2));
+        lost_realm.update();
+        assert_eq!(*heard.borrow(), vec!["Bell 1"]);
+        assert!(lost_realm.unobserve(handle).is_err());
+    }
+
+    #[test]
+    fn sent_events_wait_in_the_target_inbox() {
+        let lost_realm = LostRealm::new();
+        let bell = lost_realm.forge_new_object("Bell", (lantern(),)).unwrap();
+        let hall = lost_realm.forge_new_object("Hall", (lantern(),)).unwrap();
+        lost_realm
+            .send_destiny_rift_event(bell.id, Chime(1))
+            .unwrap();
+        lost_realm.send_destiny_rift_event(bell.id, Toll).unwrap();
+        lost_realm
+            .send_destiny_rift_event(bell.id, Chime(2))
+            .unwrap();
+
+        assert_eq!(hall.get_inbox_len(), 0);
+        assert_eq!(bell.count_inbox_events::<Chime>(), 2);
+        assert_eq!(bell.peek_inbox_event::<Chime>().unwrap().0, 1);
+        assert_eq!(bell.take_inbox_event::<Chime>().unwrap().0, 1);
+        let chimes = bell.take_inbox_events::<Chime>();
+        assert_eq!(
+            chimes.iter().map(|chime| chime.0).collect::<Vec<_>>(),
+            vec![2]
+        );
+        assert_eq!(bell.get_inbox_len(), 1);
+        assert!(bell.take_inbox_event::<Toll>().is_some());
+        assert!(matches!(
+            lost_realm.send_destiny_rift_event(uuid::Uuid::new_v4(), Toll),
+            Err(LostLandsFault::Realm(
+                LostLostLandsFaultRealm::ForgedObjectNotFound(..)
+            ))
+        ));
+    }
+
+    #[test]
+    fn destroyed_target_drops_its_inbox_and_observers() {
+        let lost_realm = LostRealm::new();
+        let bell = lost_realm.forge_new_object("Bell", (lantern(),)).unwrap();
+        let id = bell.id;
+        let handle = lost_realm.observe_forged_object::<Chime>(bell, |_, _, _| {});
+        lost_realm.send_destiny_rift_event(id, Chime(1)).unwrap();
+        let bell = lost_realm.destroy_forged_object("Bell").unwrap();
+        assert_eq!(bell.get_inbox_len(), 0);
+        assert!(lost_realm.unobserve(handle).is_err());
+        assert!(lost_realm.send_destiny_rift_event(id, Chime(2)).is_err());
+    }
+
+    fn count_chimes(lost_realm: &LostRealm) -> Rc<RefCell<u32>> {
+        let chimes = Rc::new(RefCell::new(0));
+        lost_realm.observe::<Chime>({
+            let chimes = chimes.clone();
+            move |_, _| *chimes.borrow_mut() += 1
+        });
+        chimes
+    }
+
+    #[test]
+    fn delayed_events_follow_scaled_time() {
+        let mut lost_realm = LostRealm::new();
+        let chimes = count_chimes(&lost_realm);
+        lost_realm.set_time_scale(0.5);
+        lost_realm.add_destiny_rift_event_after(Chime(1), 1.0);
+
+        lost_realm.eonforge.borrow_mut().update(1.0);
+        lost_realm.update();
+        assert_eq!(*chimes.borrow(), 0);
+        assert_eq!(lost_realm.count_scheduled_destiny_rift_events(), 1);
+
+        lost_realm.eonforge.borrow_mut().update(2.0);
+        lost_realm.update();
+        assert_eq!(lost_realm.get_unscaled_delta_time(), 1.0);
+        assert_eq!(*chimes.borrow(), 1);
+        assert_eq!(lost_realm.count_scheduled_destiny_rift_events(), 0);
+    }
+
+    #[test]
+    fn frame_scheduled_events_can_be_cancelled() {
+        let mut lost_realm = LostRealm::new();
+        let chimes = count_chimes(&lost_realm);
+        let handle = lost_realm.add_destiny_rift_event_after_frames(Chime(1), 2);
+        let cancelled = lost_realm.add_destiny_rift_event_after_frames(Chime(2), 1);
+        lost_realm
+            .cancel_scheduled_destiny_rift_event(cancelled)
+            .unwrap();
+        assert!(lost_realm
+            .cancel_scheduled_destiny_rift_event(cancelled)
+            .is_err());
+
+        lost_realm.update();
+        assert_eq!(*chimes.borrow(), 0);
+        assert!(lost_realm.is_destiny_rift_event_scheduled(handle));
+        lost_realm.update();
+        assert_eq!(*chimes.borrow(), 1);
+        assert!(!lost_realm.is_destiny_rift_event_scheduled(handle));
+    }
+
+    #[test]
+    fn events_sent_from_other_threads_arrive_on_update() {
+        let mut lost_realm = LostRealm::new();
+        let chimes = count_chimes(&lost_realm);
+        let sender = lost_realm.get_realm_sender();
+        std::thread::spawn(move || {
+            for chime in 0..3 {
+                sender.send(Chime(chime)).unwrap();
+            }
+        })
+        .join()
+        .unwrap();
+        assert_eq!(lost_realm.count_destiny_rift_events::<Chime>(), 0);
+        lost_realm.update();
+        assert_eq!(*chimes.borrow(), 3);
+    }
+
+    #[test]
+    fn sending_to_a_dropped_realm_is_a_fault() {
+        let sender = LostRealm::new().get_realm_sender();
+        assert!(matches!(
+            sender.send(Chime(1)),
+            Err(LostLostLandsFaultDestinyRift::RealmClosed(name)) if name.ends_with("Chime")
+        ));
+    }
+
+    type RiftLog = Rc<RefCell<Vec<String>>>;
+
+    // logs where the event went and stops it in `stop_in`
+    struct Listener {
+        father: Option<String>,
+        label: &'static str,
+        stop_in: Option<RiftPhase>,
+        log: RiftLog,
+    }
+    test_trait!(Listener);
+    impl ForgedTrait for Listener {
+        fn on_rift_capture(
+            &mut self,
+            propagation: &mut RiftPropagation,
+            _lost_realm: &mut LostRealm,
+        ) {
+            self.hear(propagation);
+        }
+
+        fn on_rift(&mut self, propagation: &mut RiftPropagation, _lost_realm: &mut LostRealm) {
+            self.hear(propagation);
+        }
+    }
+
+    impl Listener {
+        fn hear(&mut self, propagation: &mut RiftPropagation) {
+            let phase = propagation.get_phase();
+            self.log
+                .borrow_mut()
+                .push(format!("{:?} {}", phase, self.label));
+            if self.stop_in == Some(phase) {
+                propagation.handle();
+                propagation.stop_propagation();
+            }
+        }
+    }
+
+    // a second trait on each object
+    struct Echo {
+        father: Option<String>,
+        log: RiftLog,
+    }
+    test_trait!(Echo);
+    impl ForgedTrait for Echo {
+        fn on_rift(&mut self, propagation: &mut RiftPropagation, _lost_realm: &mut LostRealm) {
+            self.log
+                .borrow_mut()
+                .push(format!("{:?} echo", propagation.get_phase()));
+        }
+    }
+
+    // root <- panel <- button, returns the log and the button
+    fn hierarchy(lost_realm: &LostRealm, stop_in: [Option<RiftPhase>; 3]) -> (RiftLog, uuid::Uuid) {
+        let log = RiftLog::default();
+        let mut parent: Option<uuid::Uuid> = None;
+        for (label, stop_in) in ["root", "panel", "button"].into_iter().zip(stop_in) {
+            let listener = Listener {
+                father: None,
+                label,
+                stop_in,
+                log: log.clone(),
+            };
+            let echo = Echo {
+                father: None,
+                log: log.clone(),
+            };
+            let id = lost_realm
+                .forge_new_object(label, (listener, echo))
+                .unwrap()
+                .id;
+            if let Some(parent) = parent {
+                let transform = lost_realm
+                    .get_forged_object_by_id(parent)
+                    .unwrap()
+                    .transform
+                    .clone();
+                let object = lost_realm.get_mut_forged_object_by_id(id).unwrap();
+                object.set_transform_parent(transform).unwrap();
+            }
+            parent = Some(id);
+        }
+        (log, parent.unwrap())
+    }
+
+    #[test]
+    fn bubbling_captures_down_then_bubbles_up() {
+        let mut lost_realm = LostRealm::new();
+        let (log, button) = hierarchy(&lost_realm, [None, None, None]);
+        let propagation = lost_realm
+            .bubble_destiny_rift_event(button, Chime(1))
+            .unwrap();
+        assert!(!propagation.is_handled());
+        let expected = [
+            "Capture root",
+            "Capture panel",
+            "Target button",
+            "Target echo",
+            "Bubble panel",
+            "Bubble echo",
+            "Bubble root",
+            "Bubble echo",
+        ];
+        assert_eq!(*log.borrow(), expected);
+    }
+
+    #[test]
+    fn stopping_in_capture_keeps_the_event_from_the_target() {
+        let mut lost_realm = LostRealm::new();
+        let (log, button) = hierarchy(&lost_realm, [None, Some(RiftPhase::Capture), None]);
+        let propagation = lost_realm
+            .bubble_destiny_rift_event(button, Chime(1))
+            .unwrap();
+        assert!(propagation.is_handled() && propagation.is_stopped());
+        assert_eq!(*log.borrow(), ["Capture root", "Capture panel"]);
+    }
+
+    #[test]
+    fn stopping_on_the_target_still_reaches_its_other_traits() {
+        let mut lost_realm = LostRealm::new();
+        let (log, button) = hierarchy(&lost_realm, [None, None, Some(RiftPhase::Target)]);
+        lost_realm
+            .bubble_destiny_rift_event(button, Chime(1))
+            .unwrap();
+        let expected = [
+            "Capture root",
+            "Capture panel",
+            "Target button",
+            "Target echo",
+        ];
+        assert_eq!(*log.borrow(), expected);
+    }
+
+    #[test]
+    fn stopping_while_bubbling_skips_the_rest_of_the_parents() {
+        let mut lost_realm = LostRealm::new();
+        let (log, button) = hierarchy(&lost_realm, [None, Some(RiftPhase::Bubble), None]);
+        lost_realm
+            .bubble_destiny_rift_event(button, Chime(1))
+            .unwrap();
+        assert_eq!(log.borrow().last().unwrap(), "Bubble echo");
+        assert!(!log.borrow().contains(&"Bubble root".to_string()));
+    }
+
+    #[cfg(feature = "hot_reload")]
+    mod arcane_weft_library {
+        use crate::{
+            arcane_weft_library::{ArcaneWeftLibrary, LibraryImage},
+            forged_trait::ForgedTrait,
+            lost_lands_fault::LostLostLandsFaultArcaneWeft,
+            lost_realm::LostRealm,
+            soul_thread::{EssenceAspect, SoulThread},
+            testing::test_trait,
+        };
+
+        struct Ember {
+            father: Option<String>,
+        }
+        test_trait!(Ember);
+        impl ForgedTrait for Ember {}
+
+        struct Ash {
+            father: Option<String>,
+        }
+        test_trait!(Ash);
+        impl ForgedTrait for Ash {}
+
+        fn vtable_range<T: ?Sized>(ptr: *const T) -> (usize, usize) {
+            let vtable = unsafe { std::mem::transmute_copy::<*const T, (usize, usize)>(&ptr).1 };
+            (vtable, vtable + 1)
+        }
+
+        #[test]
+        fn unknown_libraries_are_reported() {
+            let mut lost_realm = LostRealm::new();
+            assert!(matches!(
+                lost_realm.load_arcane_weft_library("/nonexistent/libnothing.so"),
+                Err(LostLostLandsFaultArcaneWeft::LibraryLoad(..))
+            ));
+            assert!(lost_realm.get_arcane_weft_libraries().is_empty());
+            assert!(matches!(
+                lost_realm.unload_arcane_weft_library("/nonexistent/libnothing.so"),
+                Err(LostLostLandsFaultArcaneWeft::LibraryNotLoaded(..))
+            ));
+            assert!(matches!(
+                lost_realm.reload_arcane_weft_library("/nonexistent/libnothing.so"),
+                Err(LostLostLandsFaultArcaneWeft::LibraryNotLoaded(..))
+            ));
+            assert!(lost_realm
+                .reload_changed_arcane_weft_libraries()
+                .unwrap()
+                .is_empty());
+        }
+
+        #[test]
+        fn unloading_purges_everything_backed_by_the_library() {
+            let mut lost_realm = LostRealm::new();
+            let plugin_id = lost_realm
+                .forge_new_object("Plugin", (Ash { father: None },))
+                .unwrap()
+                .id;
+            lost_realm
+                .forge_new_object("Host", (Ember { father: None }, Ash { father: None }))
+                .unwrap();
+            lost_realm.add_soul_thread(SoulThread::new("plugin", || EssenceAspect::Finished));
+            lost_realm.add_soul_thread(SoulThread::new("host", || EssenceAspect::Finished));
+
+            let host = lost_realm.get_forged_object("Host").unwrap();
+            let ember = host
+                .forged_traits
+                .iter()
+                .find(|forged_trait| forged_trait.borrow().as_any().is::<Ember>())
+                .unwrap();
+            let soul_threads = lost_realm.soul_threads_manager.borrow();
+            let plugin_thread = soul_threads
+                .get_soul_threads()
+                .iter()
+                .find(|thread| thread.get_name() == "plugin")
+                .unwrap();
+            let image = LibraryImage::from_ranges(vec![
+                vtable_range(&**ember),
+                vtable_range(plugin_thread.generator_ptr().unwrap()),
+            ]);
+            drop(soul_threads);
+
+            let path = std::path::Path::new("/plugins/libember.so");
+            lost_realm
+                .arcane_weft_libraries
+                .push(ArcaneWeftLibrary::detached(path, image, vec![plugin_id]));
+            lost_realm.unload_arcane_weft_library(path).unwrap();
+
+            assert!(lost_realm.get_arcane_weft_libraries().is_empty());
+            assert!(lost_realm.get_forged_object_by_id(plugin_id).is_none());
+            let host = lost_realm.get_forged_object("Host").unwrap();
+            assert!(!host.has_trait::<Ember>());
+            assert!(host.has_trait::<Ash>());
+            let soul_threads = lost_realm.soul_threads_manager.borrow();
+            let names = soul_threads
+                .get_soul_threads()
+                .iter()
+                .map(|thread| thread.get_name())
+                .collect::<Vec<_>>();
+            assert_eq!(names, vec!["host"]);
+        }
+    }
+
+    #[test]
+    fn step_saturates_instead_of_overflowing() {
+        let mut lost_realm = LostRealm::new();
+        lost_realm.pause();
+        lost_realm.step(u32::MAX);
+        lost_realm.step(u32::MAX);
+        assert_eq!(lost_realm.pending_steps, u32::MAX);
+    }
+}
@@ -0,0 +1,272 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::arcane_weft::host_bindings;
+
+/// How important an omen is, omens below the threshold set with `set_omen_level` are skipped
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OmenLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl OmenLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OmenLevel::Trace => "trace",
+            OmenLevel::Debug => "debug",
+            OmenLevel::Info => "info",
+            OmenLevel::Warn => "warn",
+            OmenLevel::Error => "error",
+        }
+    }
+}
+
+/// Part of the engine an omen comes from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OmenCategory {
+    Realm,
+    ForgedObject,
+    DestinyRift,
+    SoulThread,
+    Transform,
+    ArcaneWeft,
+    Script,
+}
+
+impl OmenCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OmenCategory::Realm => "realm",
+            OmenCategory::ForgedObject => "forged_object",
+            OmenCategory::DestinyRift => "destiny_rift",
+            OmenCategory::SoulThread => "soul_thread",
+            OmenCategory::Transform => "transform",
+            OmenCategory::ArcaneWeft => "arcane_weft",
+            OmenCategory::Script => "script",
+        }
+    }
+}
+
+// alias for a diagnostic message
+#[derive(Clone, Debug, PartialEq)]
+pub struct Omen {
+    pub level: OmenLevel,
+    pub category: OmenCategory,
+    pub message: String,
+}
+
+impl std::fmt::Display for Omen {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "[{} {}] {}",
+            self.level.as_str(),
+            self.category.as_str(),
+            self.message
+        )
+    }
+}
+
+/// Receives every omen that passes the level and category filters
+pub trait OmenSink {
+    fn receive(&mut self, omen: &Omen);
+}
+
+/// Writes omens to stderr
+#[derive(Default)]
+pub struct ConsoleOmenSink;
+
+impl OmenSink for ConsoleOmenSink {
+    fn receive(&mut self, omen: &Omen) {
+        eprintln!("{}", omen);
+    }
+}
+
+/// Keeps omens in memory, clones share the same omens so one can be kept to assert against
+#[derive(Clone, Default)]
+pub struct CaptureOmenSink {
+    omens: Rc<RefCell<Vec<Omen>>>,
+}
+
+impl CaptureOmenSink {
+    pub fn new() -> CaptureOmenSink {
+        CaptureOmenSink::default()
+    }
+
+    pub fn get_omens(&self) -> Vec<Omen> {
+        self.omens.borrow().clone()
+    }
+
+    pub fn take_omens(&self) -> Vec<Omen> {
+        std::mem::take(&mut self.omens.borrow_mut())
+    }
+
+    pub fn clear(&self) {
+        self.omens.borrow_mut().clear();
+    }
+
+    /// Whether an omen of the level and category contains `text`
+    pub fn contains(&self, level: OmenLevel, category: OmenCategory, text: &str) -> bool {
+        self.omens.borrow().iter().any(|omen| {
+            omen.level == level && omen.category == category && omen.message.contains(text)
+        })
+    }
+
+    pub fn count(&self, category: OmenCategory) -> usize {
+        self.omens
+            .borrow()
+            .iter()
+            .filter(|omen| omen.category == category)
+            .count()
+    }
+}
+
+impl OmenSink for CaptureOmenSink {
+    fn receive(&mut self, omen: &Omen) {
+        self.omens.borrow_mut().push(omen.clone());
+    }
+}
+
+/// Returned by `add_omen_sink` to remove the sink later
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OmenSinkId(usize);
+
+// alias for the diagnostics dispatcher
+struct Oracle {
+    level: OmenLevel,
+    silenced: Vec<OmenCategory>,
+    sinks: Vec<(OmenSinkId, Box<dyn OmenSink>)>,
+    next_id: usize,
+}
+
+thread_local! {
+    // realms are bound to the thread that created them, so are their omens
+    static ORACLE: RefCell<Oracle> = RefCell::new(Oracle {
+        level: OmenLevel::Info,
+        silenced: Vec::new(),
+        sinks: Vec::new(),
+        next_id: 0,
+    });
+}
+
+// the oracle of this thread, the host's one inside a plugin
+fn with_oracle<R>(f: impl FnOnce(&RefCell<Oracle>) -> R) -> R {
+    match host_bindings() {
+        // the host is built like the plugin, so is its oracle
+        Some(host) => f(unsafe { &*((host.oracle)() as *const RefCell<Oracle>) }),
+        None => ORACLE.with(f),
+    }
+}
+
+#[cfg(feature = "hot_reload")]
+pub(crate) extern "C" fn local_oracle() -> *const std::ffi::c_void {
+    ORACLE.with(|oracle| oracle as *const RefCell<Oracle> as *const std::ffi::c_void)
+}
+
+/// Sends the omens of this thread to `sink` as well. Nothing is reported until a sink is added.
+pub fn add_omen_sink(sink: impl OmenSink + 'static) -> OmenSinkId {
+    with_oracle(|oracle| {
+        let mut oracle = oracle.borrow_mut();
+        let id = OmenSinkId(oracle.next_id);
+        oracle.next_id += 1;
+        oracle.sinks.push((id, Box::new(sink)));
+        id
+    })
+}
+
+pub fn remove_omen_sink(id: OmenSinkId) {
+    with_oracle(|oracle| oracle.borrow_mut().sinks.retain(|(sink, _)| *sink != id));
+}
+
+pub fn clear_omen_sinks() {
+    with_oracle(|oracle| oracle.borrow_mut().sinks.clear());
+}
+
+/// Lowest level reported, `Info` by default
+pub fn set_omen_level(level: OmenLevel) {
+    with_oracle(|oracle| oracle.borrow_mut().level = level);
+}
+
+pub fn set_omen_category(category: OmenCategory, enabled: bool) {
+    with_oracle(|oracle| {
+        let mut oracle = oracle.borrow_mut();
+        oracle.silenced.retain(|silenced| *silenced != category);
+        if !enabled {
+            oracle.silenced.push(category);
+        }
+    });
+}
+
+/// Whether an omen would reach any sink
+pub fn is_omen_enabled(level: OmenLevel, category: OmenCategory) -> bool {
+    with_oracle(|oracle| {
+        let oracle = oracle.borrow();
+        !oracle.sinks.is_empty() && level >= oracle.level && !oracle.silenced.contains(&category)
+    })
+}
+
+/// Reports an omen, the message is only built when a sink will receive it
+pub fn foretell(level: OmenLevel, category: OmenCategory, message: impl FnOnce() -> String) {
+    if !is_omen_enabled(level, category) {
+        return;
+    }
+    let omen = Omen {
+        level,
+        category,
+        message: message(),
+    };
+    // sinks are taken out so they can report omens of their own
+    let mut sinks = with_oracle(|oracle| std::mem::take(&mut oracle.borrow_mut().sinks));
+    for (_, sink) in sinks.iter_mut() {
+        sink.receive(&omen);
+    }
+    with_oracle(|oracle| {
+        let mut oracle = oracle.borrow_mut();
+        sinks.append(&mut oracle.sinks);
+        oracle.sinks = sinks;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn omens_below_the_level_or_silenced_are_skipped() {
+        let sink = CaptureOmenSink::new();
+        let sink_id = add_omen_sink(sink.clone());
+        set_omen_level(OmenLevel::Info);
+        foretell(OmenLevel::Debug, OmenCategory::Realm, || {
+            "hidden".to_string()
+        });
+        foretell(OmenLevel::Warn, OmenCategory::Realm, || "shown".to_string());
+        set_omen_category(OmenCategory::Realm, false);
+        foretell(OmenLevel::Error, OmenCategory::Realm, || {
+            "silenced".to_string()
+        });
+        set_omen_category(OmenCategory::Realm, true);
+        remove_omen_sink(sink_id);
+        foretell(OmenLevel::Error, OmenCategory::Realm, || {
+            "unheard".to_string()
+        });
+        let messages = sink
+            .get_omens()
+            .into_iter()
+            .map(|omen| omen.message)
+            .collect::<Vec<_>>();
+        assert_eq!(messages, vec!["shown"]);
+    }
+
+    #[test]
+    fn messages_are_only_built_for_a_sink() {
+        let mut built = false;
+        foretell(OmenLevel::Error, OmenCategory::Script, || {
+            built = true;
+            String::new()
+        });
+        assert!(!built);
+    }
+}
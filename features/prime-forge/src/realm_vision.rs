@@ -0,0 +1,386 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write as _,
+    time::Duration,
+};
+
+use crate::{
+    destiny_rift::DestinyRiftManager,
+    forged_object::ForgedObject,
+    runic_essence::{escape_json, RunicField, RunicValue},
+    soul_thread::{EssenceAspect, SoulThreadManager},
+};
+
+/// Name of the traits that were running while the realm was dumped
+pub const BORROWED_TRAIT: &str = "<borrowed>";
+
+pub struct TraitVision {
+    pub name: &'static str,
+    pub fields: Vec<RunicField>,
+}
+
+pub struct ObjectVision {
+    pub name: String,
+    pub id: uuid::Uuid,
+    pub position: [f32; 3],
+    pub rotation: [f32; 3],
+    pub scale: [f32; 3],
+    pub traits: Vec<TraitVision>,
+    pub children: Vec<ObjectVision>,
+}
+
+pub struct RiftVision {
+    pub name: &'static str,
+    pub events: Vec<Vec<RunicField>>,
+}
+
+pub struct SoulThreadVision {
+    pub id: u64,
+    pub name: String,
+    pub state: EssenceAspect,
+    pub remaining_wait: f32,
+    pub paused: bool,
+}
+
+// alias for a realm dump
+pub struct RealmVision {
+    pub delta_time: f32,
+    pub time_elapsed: Duration,
+    pub objects: Vec<ObjectVision>,
+    pub destiny_rifts: Vec<RiftVision>,
+    pub soul_threads: Vec<SoulThreadVision>,
+}
+
+impl RealmVision {
+    pub(crate) fn scry(
+        forged_objects: &[ForgedObject],
+        destiny_rift_manager: &DestinyRiftManager,
+        soul_threads_manager: &SoulThreadManager,
+        delta_time: f32,
+        time_elapsed: Duration,
+    ) -> RealmVision {
+        let by_transform = forged_objects
+            .iter()
+            .map(|object| (object.transform.borrow().get_id(), object))
+            .collect::<HashMap<_, _>>();
+        let mut visited = HashSet::new();
+        let objects = forged_objects
+            .iter()
+            .filter(|object| {
+                object
+                    .transform
+                    .borrow()
+                    .get_parent()
+                    .is_none_or(|parent| !by_transform.contains_key(&parent.borrow().get_id()))
+            })
+            .filter_map(|object| Self::scry_object(object, &by_transform, &mut visited))
+            .collect();
+
+        let mut destiny_rifts = destiny_rift_manager
+            .events
+            .values()
+            .filter_map(|events| {
+                let name = events.iter().next()?.rift_name();
+                Some(RiftVision {
+                    name,
+                    events: events.iter().map(|event| event.runic_fields()).collect(),
+                })
+            })
+            .collect::<Vec<_>>();
+        destiny_rifts.sort_by_key(|rift| rift.name);
+
+        let soul_threads = soul_threads_manager
+            .get_soul_thread_handles()
+            .iter()
+            .map(|handle| SoulThreadVision {
+                id: handle.get_id(),
+                name: handle.get_name(),
+                state: handle.get_state(),
+                remaining_wait: handle.get_remaining_wait(),
+                paused: handle.is_paused(),
+            })
+            .collect();
+
+        RealmVision {
+            delta_time,
+            time_elapsed,
+            objects,
+            destiny_rifts,
+            soul_threads,
+        }
+    }
+
+    fn scry_object(
+        object: &ForgedObject,
+        by_transform: &HashMap<uuid::Uuid, &ForgedObject>,
+        visited: &mut HashSet<uuid::Uuid>,
+    ) -> Option<ObjectVision> {
+        if !visited.insert(object.id) {
+            return None;
+        }
+        let transform = object.transform.borrow();
+        let children = transform
+            .get_children()
+            .iter()
+            .filter_map(|child| by_transform.get(&child.borrow().get_id()).copied())
+            .filter_map(|child| Self::scry_object(child, by_transform, visited))
+            .collect();
+        let traits = object
+            .forged_traits
+            .iter()
+            .map(|forged_trait| match forged_trait.try_borrow() {
+                Ok(forged_trait) => TraitVision {
+                    name: forged_trait.trait_name(),
+                    fields: forged_trait.runic_fields(),
+                },
+                // the trait dumping the realm, or one it is running
+                Err(_) => TraitVision {
+                    name: BORROWED_TRAIT,
+                    fields: Vec::new(),
+                },
+            })
+            .collect();
+
+        Some(ObjectVision {
+            name: object.name.clone(),
+            id: object.id,
+            position: transform.position.into(),
+            rotation: transform.rotation.into(),
+            scale: transform.scale.into(),
+            traits,
+            children,
+        })
+    }
+
+    pub fn to_text(&self) -> String {
+        self.to_string()
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        let _ = write!(
+            json,
+            "{{\"delta_time\":{},\"time_elapsed\":{},\"objects\":[",
+            json_float(self.delta_time),
+            self.time_elapsed.as_secs_f64()
+        );
+        write_separated(&mut json, &self.objects, write_object_json);
+        json.push_str("],\"destiny_rifts\":[");
+        write_separated(&mut json, &self.destiny_rifts, |json, rift| {
+            let _ = write!(
+                json,
+                "{{\"name\":\"{}\",\"count\":{},\"events\":[",
+                escape_json(rift.name),
+                rift.events.len()
+            );
+            write_separated(json, &rift.events, |json, fields| {
+                write_fields_json(json, fields)
+            });
+            json.push_str("]}");
+        });
+        json.push_str("],\"soul_threads\":[");
+        write_separated(&mut json, &self.soul_threads, |json, thread| {
+            let _ = write!(
+                json,
+                concat!(
+                    "{{\"id\":{},\"name\":\"{}\",\"state\":\"{}\",",
+                    "\"remaining_wait\":{},\"paused\":{}}}"
+                ),
+                thread.id,
+                escape_json(&thread.name),
+                essence_aspect_name(&thread.state),
+                json_float(thread.remaining_wait),
+                thread.paused
+            );
+        });
+        json.push_str("]}");
+        json
+    }
+}
+
+impl std::fmt::Display for RealmVision {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Lost Realm (delta time: {}, elapsed: {:?})",
+            self.delta_time, self.time_elapsed
+        )?;
+        writeln!(f, "Forged Objects")?;
+        for object in &self.objects {
+            write_object_text(f, object, 1)?;
+        }
+        writeln!(f, "Destiny Rifts")?;
+        for rift in &self.destiny_rifts {
+            writeln!(f, "  {} x{}", rift.name, rift.events.len())?;
+            for fields in rift.events.iter().filter(|fields| !fields.is_empty()) {
+                writeln!(f, "    {}", fields_text(fields))?;
+            }
+        }
+        writeln!(f, "Soul Threads")?;
+        for thread in &self.soul_threads {
+            writeln!(
+                f,
+                "  #{} {} {}{} (remaining wait: {}s)",
+                thread.id,
+                thread.name,
+                essence_aspect_name(&thread.state),
+                if thread.paused { " paused" } else { "" },
+                thread.remaining_wait
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn write_object_text(
+    f: &mut std::fmt::Formatter,
+    object: &ObjectVision,
+    depth: usize,
+) -> std::fmt::Result {
+    let indent = "  ".repeat(depth);
+    writeln!(f, "{}{} <{}>", indent, object.name, object.id)?;
+    writeln!(
+        f,
+        "{}  position: {:?} rotation: {:?} scale: {:?}",
+        indent, object.position, object.rotation, object.scale
+    )?;
+    for forged_trait in &object.traits {
+        writeln!(
+            f,
+            "{}  {} {}",
+            indent,
+            forged_trait.name,
+            fields_text(&forged_trait.fields)
+        )?;
+    }
+    for child in &object.children {
+        write_object_text(f, child, depth + 1)?;
+    }
+    Ok(())
+}
+
+fn fields_text(fields: &[RunicField]) -> String {
+    let fields = fields
+        .iter()
+        .map(|field| format!("{}: {}", field.name, field.value))
+        .collect::<Vec<_>>();
+    format!("{{ {} }}", fields.join(", "))
+}
+
+fn essence_aspect_name(state: &EssenceAspect) -> &'static str {
+    match state {
+        EssenceAspect::Running => "Running",
+        EssenceAspect::Yielded(_) => "Yielded",
+        EssenceAspect::Awaiting(_) => "Awaiting",
+        EssenceAspect::Finished => "Finished",
+    }
+}
+
+// non-finite values are written as null, which JSON can represent
+fn json_float(value: f32) -> String {
+    RunicValue::Float(value as f64).to_json()
+}
+
+fn json_vector(values: &[f32]) -> String {
+    RunicValue::Vector(values.to_vec()).to_json()
+}
+
+fn write_separated<T>(json: &mut String, items: &[T], mut write: impl FnMut(&mut String, &T)) {
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        write(json, item);
+    }
+}
+
+fn write_fields_json(json: &mut String, fields: &[RunicField]) {
+    json.push('{');
+    write_separated(json, fields, |json, field| {
+        let _ = write!(
+            json,
+            "\"{}\":{}",
+            escape_json(&field.name),
+            field.value.to_json()
+        );
+    });
+    json.push('}');
+}
+
+fn write_object_json(json: &mut String, object: &ObjectVision) {
+    let _ = write!(
+        json,
+        "{{\"name\":\"{}\",\"id\":\"{}\",\"position\":{},\"rotation\":{},\"scale\":{},\"traits\":[",
+        escape_json(&object.name),
+        object.id,
+        json_vector(&object.position),
+        json_vector(&object.rotation),
+        json_vector(&object.scale)
+    );
+    write_separated(json, &object.traits, |json, forged_trait| {
+        let _ = write!(
+            json,
+            "{{\"name\":\"{}\",\"fields\":",
+            escape_json(forged_trait.name)
+        );
+        write_fields_json(json, &forged_trait.fields);
+        json.push('}');
+    });
+    json.push_str("],\"children\":[");
+    write_separated(json, &object.children, write_object_json);
+    json.push_str("]}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{forged_trait::ForgedTrait, lost_realm::LostRealm, testing::test_trait};
+
+    #[derive(Default)]
+    struct Mirror {
+        father: Option<String>,
+        seen: Vec<&'static str>,
+    }
+    test_trait!(Mirror);
+
+    impl ForgedTrait for Mirror {
+        fn update(&mut self, lost_realm: &mut LostRealm, _dt: f32) {
+            let vision = lost_realm.dump();
+            self.seen = vision.objects[0]
+                .traits
+                .iter()
+                .map(|forged_trait| forged_trait.name)
+                .collect();
+        }
+    }
+
+    #[test]
+    fn dump_from_a_trait_update_marks_it_borrowed() {
+        let mut lost_realm = LostRealm::new();
+        lost_realm
+            .forge_new_object("Mirror", (Mirror::default(),))
+            .unwrap();
+        lost_realm.update();
+        let mirror = lost_realm.get_trait_by_type::<Mirror>().unwrap();
+        assert_eq!(mirror.seen, vec![BORROWED_TRAIT]);
+        let vision = lost_realm.dump();
+        assert_eq!(
+            vision.objects[0].traits[0].name,
+            std::any::type_name::<Mirror>()
+        );
+    }
+
+    #[test]
+    fn non_finite_values_are_written_as_null() {
+        let lost_realm = LostRealm::new();
+        let object = lost_realm
+            .forge_new_object("Lost", (Mirror::default(),))
+            .unwrap();
+        object.transform.borrow_mut().position.x = f32::NAN;
+        object.transform.borrow_mut().scale.y = f32::INFINITY;
+        let json = lost_realm.dump().to_json();
+        assert!(json.contains("\"position\":[null,0.0,0.0]"), "{}", json);
+        assert!(json.contains("\"scale\":[1.0,null,1.0]"), "{}", json);
+        assert!(!json.contains("NaN") && !json.contains("inf"), "{}", json);
+    }
+}
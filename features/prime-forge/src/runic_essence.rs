@@ -0,0 +1,213 @@
+use std::{borrow::Cow, fmt::Write as _};
+
+use nalgebra_glm as glm;
+
+/// Reflected value of a field marked with `#[runic]`
+#[derive(Clone, Debug, PartialEq)]
+pub enum RunicValue {
+    Empty,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Vector(Vec<f32>),
+}
+
+impl RunicValue {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RunicValue::Empty => "empty",
+            RunicValue::Bool(_) => "bool",
+            RunicValue::Int(_) => "int",
+            RunicValue::Float(_) => "float",
+            RunicValue::Text(_) => "text",
+            RunicValue::Vector(_) => "vector",
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        match self {
+            RunicValue::Empty => "null".to_string(),
+            RunicValue::Bool(value) => value.to_string(),
+            RunicValue::Int(value) => value.to_string(),
+            RunicValue::Float(value) if value.is_finite() => format!("{:?}", value),
+            RunicValue::Float(_) => "null".to_string(),
+            RunicValue::Text(value) => format!("\"{}\"", escape_json(value)),
+            RunicValue::Vector(values) => {
+                let values = values
+                    .iter()
+                    .map(|value| RunicValue::Float(*value as f64).to_json())
+                    .collect::<Vec<_>>();
+                format!("[{}]", values.join(","))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for RunicValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RunicValue::Empty => write!(f, "None"),
+            RunicValue::Bool(value) => write!(f, "{}", value),
+            RunicValue::Int(value) => write!(f, "{}", value),
+            RunicValue::Float(value) => write!(f, "{:?}", value),
+            RunicValue::Text(value) => write!(f, "{:?}", value),
+            RunicValue::Vector(values) => write!(f, "{:?}", values),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunicField {
+    pub name: Cow<'static, str>,
+    pub value: RunicValue,
+}
+
+impl RunicField {
+    pub fn new(name: impl Into<Cow<'static, str>>, value: RunicValue) -> RunicField {
+        RunicField {
+            name: name.into(),
+            value,
+        }
+    }
+}
+
+/// Types that can be read and written through `#[runic]` fields
+pub trait RunicEssence: Sized {
+    fn to_runic(&self) -> RunicValue;
+    fn from_runic(value: &RunicValue) -> Option<Self>;
+}
+
+impl RunicEssence for bool {
+    fn to_runic(&self) -> RunicValue {
+        RunicValue::Bool(*self)
+    }
+
+    fn from_runic(value: &RunicValue) -> Option<Self> {
+        match value {
+            RunicValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_runic_essence_int {
+    ($($ty: ty),*) => {
+        $(impl RunicEssence for $ty {
+            // values past i64::MAX are kept as text rather than wrapped
+            fn to_runic(&self) -> RunicValue {
+                i64::try_from(*self)
+                    .map_or_else(|_| RunicValue::Text(self.to_string()), RunicValue::Int)
+            }
+
+            fn from_runic(value: &RunicValue) -> Option<Self> {
+                match value {
+                    RunicValue::Int(value) => (*value).try_into().ok(),
+                    RunicValue::Text(value) => value.parse().ok(),
+                    _ => None,
+                }
+            }
+        })*
+    };
+}
+impl_runic_essence_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+macro_rules! impl_runic_essence_float {
+    ($($ty: ty),*) => {
+        $(impl RunicEssence for $ty {
+            fn to_runic(&self) -> RunicValue {
+                RunicValue::Float(*self as f64)
+            }
+
+            fn from_runic(value: &RunicValue) -> Option<Self> {
+                match value {
+                    RunicValue::Float(value) => Some(*value as $ty),
+                    RunicValue::Int(value) => Some(*value as $ty),
+                    _ => None,
+                }
+            }
+        })*
+    };
+}
+impl_runic_essence_float!(f32, f64);
+
+impl RunicEssence for String {
+    fn to_runic(&self) -> RunicValue {
+        RunicValue::Text(self.clone())
+    }
+
+    fn from_runic(value: &RunicValue) -> Option<Self> {
+        match value {
+            RunicValue::Text(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl<T: RunicEssence> RunicEssence for Option<T> {
+    fn to_runic(&self) -> RunicValue {
+        match self {
+            Some(value) => value.to_runic(),
+            None => RunicValue::Empty,
+        }
+    }
+
+    fn from_runic(value: &RunicValue) -> Option<Self> {
+        match value {
+            RunicValue::Empty => Some(None),
+            value => T::from_runic(value).map(Some),
+        }
+    }
+}
+
+macro_rules! impl_runic_essence_vector {
+    ($($ty: ty => $len: expr),*) => {
+        $(impl RunicEssence for $ty {
+            fn to_runic(&self) -> RunicValue {
+                RunicValue::Vector(self.iter().copied().collect())
+            }
+
+            fn from_runic(value: &RunicValue) -> Option<Self> {
+                match value {
+                    RunicValue::Vector(values) if values.len() == $len => {
+                        Some(<$ty>::from_column_slice(values))
+                    }
+                    _ => None,
+                }
+            }
+        })*
+    };
+}
+impl_runic_essence_vector!(glm::Vec2 => 2, glm::Vec3 => 3, glm::Vec4 => 4);
+
+pub(crate) fn escape_json(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integers_past_i64_stay_unwrapped() {
+        assert_eq!(7u64.to_runic(), RunicValue::Int(7));
+        let value = u64::MAX.to_runic();
+        assert_eq!(value, RunicValue::Text(u64::MAX.to_string()));
+        assert_eq!(u64::from_runic(&value), Some(u64::MAX));
+        assert_eq!(i64::from_runic(&value), None);
+    }
+}
@@ -1,122 +1,1633 @@
-#[derive(Clone, Copy, PartialEq, PartialOrd)]
+use std::{
+    any::TypeId,
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+
+#[cfg(feature = "hot_reload")]
+use crate::arcane_weft_library::LibraryImage;
+#[cfg(feature = "profiling")]
+use crate::eon_ledger::{EonLedger, LedgerCategory};
+use crate::{
+    arcane_weft::host_bindings,
+    destiny_rift::{DestinyRift, DestinyRiftManager, EventReader},
+    forged_object::ForgedObject,
+    lost_lands_fault::LostLostLandsFaultSoulThread,
+    lost_realm::LostRealm,
+    omen::{foretell, OmenCategory, OmenLevel},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
 pub struct TemporalPause {
     pub amount_in_seconds: f32,
 }
 
-#[derive(Clone, Copy, PartialEq, PartialOrd)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum EssenceAspect {
+    /// Resumed again on the next update, or right away within the step budget
     Running,
     Yielded(TemporalPause),
+    Awaiting(SoulWait),
     Finished,
 }
 
+/// What a soul thread waits for before it is resumed, yielded with `EssenceAspect::Awaiting`
+#[derive(Clone)]
+pub enum SoulWait {
+    /// Scaled seconds, like `TemporalPause`
+    Seconds(f32),
+    /// Real seconds, not affected by the time scale
+    RealSeconds(f32),
+    /// Resumed on the nth next update, 0 counts as 1
+    Frames(u32),
+    /// Checked on every update, see `SoulWait::until`
+    Until(Rc<dyn Fn(&LostRealm) -> bool>),
+    /// An event of the type added after the wait started, see `SoulWait::rift`
+    Rift(TypeId, &'static str),
+    /// Another soul thread finished or stopped, see `SoulWait::soul_thread`
+    SoulThread(SoulThreadHandle),
+}
+
+impl SoulWait {
+    /// The predicate should only read the realm
+    pub fn until(predicate: impl Fn(&LostRealm) -> bool + 'static) -> SoulWait {
+        SoulWait::Until(Rc::new(predicate))
+    }
+
+    pub fn rift<T: 'static + DestinyRift>() -> SoulWait {
+        SoulWait::Rift(TypeId::of::<T>(), std::any::type_name::<T>())
+    }
+
+    pub fn soul_thread(handle: &SoulThreadHandle) -> SoulWait {
+        SoulWait::SoulThread(handle.clone())
+    }
+}
+
+impl std::fmt::Debug for SoulWait {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SoulWait::Seconds(seconds) => write!(f, "Seconds({})", seconds),
+            SoulWait::RealSeconds(seconds) => write!(f, "RealSeconds({})", seconds),
+            SoulWait::Frames(frames) => write!(f, "Frames({})", frames),
+            SoulWait::Until(_) => write!(f, "Until(..)"),
+            SoulWait::Rift(_, name) => write!(f, "Rift({})", name),
+            SoulWait::SoulThread(handle) => write!(f, "SoulThread({})", handle.get_id()),
+        }
+    }
+}
+
+impl PartialEq for SoulWait {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (SoulWait::Seconds(a), SoulWait::Seconds(b)) => a == b,
+            (SoulWait::RealSeconds(a), SoulWait::RealSeconds(b)) => a == b,
+            (SoulWait::Frames(a), SoulWait::Frames(b)) => a == b,
+            (SoulWait::Until(a), SoulWait::Until(b)) => Rc::ptr_eq(a, b),
+            (SoulWait::Rift(a, _), SoulWait::Rift(b, _)) => a == b,
+            (SoulWait::SoulThread(a), SoulWait::SoulThread(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+// a wait in progress
+enum ActiveWait {
+    Seconds(f32),
+    RealSeconds(f32),
+    Frames(u32),
+    Until(Rc<dyn Fn(&LostRealm) -> bool>),
+    // events from this sequence on end the wait
+    Rift(TypeId, usize),
+    SoulThread(SoulThreadHandle),
+}
+
+impl ActiveWait {
+    fn start(wait: SoulWait) -> ActiveWait {
+        match wait {
+            SoulWait::Seconds(seconds) => ActiveWait::Seconds(seconds),
+            SoulWait::RealSeconds(seconds) => ActiveWait::RealSeconds(seconds),
+            SoulWait::Frames(frames) => ActiveWait::Frames(frames.max(1)),
+            SoulWait::Until(predicate) => ActiveWait::Until(predicate),
+            SoulWait::Rift(type_id, _) => {
+                let sequence = with_soul_context(|context| {
+                    context
+                        .borrow()
+                        .destiny_rift_manager
+                        .as_ref()
+                        .map_or(0, |manager| manager.borrow().get_next_sequence(type_id))
+                });
+                ActiveWait::Rift(type_id, sequence)
+            }
+            SoulWait::SoulThread(handle) => ActiveWait::SoulThread(handle),
+        }
+    }
+
+    // advances the wait by one update, true once it is over
+    fn advance(&mut self) -> bool {
+        with_soul_context(|context| {
+            let context = context.borrow();
+            match self {
+                ActiveWait::Seconds(seconds) => {
+                    *seconds -= context.delta_time;
+                    *seconds <= 0.0
+                }
+                ActiveWait::RealSeconds(seconds) => {
+                    *seconds -= context.unscaled_delta_time;
+                    *seconds <= 0.0
+                }
+                ActiveWait::Frames(frames) => {
+                    *frames = frames.saturating_sub(1);
+                    *frames == 0
+                }
+                ActiveWait::Until(predicate) => {
+                    // the realm outlives the update that put it in the context
+                    let lost_realm = context.lost_realm.map(|lost_realm| unsafe { &*lost_realm });
+                    lost_realm.is_some_and(|lost_realm| predicate(lost_realm))
+                }
+                ActiveWait::Rift(type_id, sequence) => context
+                    .destiny_rift_manager
+                    .as_ref()
+                    .is_some_and(|manager| {
+                        manager.borrow().get_next_sequence(*type_id) > *sequence
+                    }),
+                ActiveWait::SoulThread(handle) => handle.is_finished(),
+            }
+        })
+    }
+
+    fn get_remaining_seconds(&self) -> f32 {
+        match self {
+            ActiveWait::Seconds(seconds) | ActiveWait::RealSeconds(seconds) => seconds.max(0.0),
+            _ => 0.0,
+        }
+    }
+}
+
+// what a soul thread runs
+enum SoulEssence {
+    Generator(Box<dyn FnMut() -> EssenceAspect + 'static>),
+    RealmGenerator(Box<dyn FnMut(&mut LostRealm) -> EssenceAspect + 'static>),
+    Future(Pin<Box<dyn Future<Output = ()> + 'static>>),
+    Weave(SoulWeave),
+}
+
+// soul threads composed into one, see `SoulThread::sequence`
+enum SoulWeave {
+    Sequence(VecDeque<SoulThread>),
+    Parallel(Vec<SoulThread>),
+    Race(Vec<SoulThread>),
+    Repeat {
+        // None repeats forever
+        remaining: Option<u32>,
+        factory: Box<dyn FnMut() -> SoulThread + 'static>,
+        current: Option<Box<SoulThread>>,
+    },
+}
+
+impl SoulWeave {
+    // resumes the inner soul threads once, true once the weave is over
+    fn tick(&mut self) -> bool {
+        match self {
+            SoulWeave::Sequence(threads) => {
+                // the next soul thread starts on the update the previous one finished
+                while let Some(thread) = threads.front_mut() {
+                    if !thread.tick_inner() {
+                        return false;
+                    }
+                    let finished = threads.pop_front().into_iter().collect();
+                    SoulThreadManager::finish(finished);
+                }
+                true
+            }
+            SoulWeave::Parallel(threads) => {
+                let (finished, running) = std::mem::take(threads)
+                    .into_iter()
+                    .map(|mut thread| (thread.tick_inner(), thread))
+                    .partition::<Vec<_>, _>(|(finished, _)| *finished);
+                *threads = running.into_iter().map(|(_, thread)| thread).collect();
+                SoulThreadManager::finish(finished.into_iter().map(|(_, thread)| thread).collect());
+                threads.is_empty()
+            }
+            SoulWeave::Race(threads) => {
+                // every soul thread is resumed so the update is the same for all of them
+                let finished = threads
+                    .iter_mut()
+                    .map(SoulThread::tick_inner)
+                    .collect::<Vec<_>>();
+                let won = finished.contains(&true);
+                if won || threads.is_empty() {
+                    // the winners finish, only the soul threads still running are stopped
+                    threads
+                        .iter_mut()
+                        .zip(finished)
+                        .filter(|(_, finished)| !finished)
+                        .for_each(|(thread, _)| thread.stop());
+                    SoulThreadManager::finish(std::mem::take(threads));
+                }
+                won || threads.is_empty()
+            }
+            SoulWeave::Repeat {
+                remaining,
+                factory,
+                current,
+            } => {
+                if *remaining == Some(0) {
+                    return true;
+                }
+                // each repetition starts on its own update
+                let thread = current.get_or_insert_with(|| Box::new(factory()));
+                if !thread.tick_inner() {
+                    return false;
+                }
+                let finished = current.take().into_iter().map(|thread| *thread).collect();
+                SoulThreadManager::finish(finished);
+                if let Some(remaining) = remaining {
+                    *remaining -= 1;
+                    return *remaining == 0;
+                }
+                false
+            }
+        }
+    }
+
+    fn get_remaining_wait(&self) -> f32 {
+        match self {
+            SoulWeave::Sequence(threads) => {
+                threads.front().map_or(0.0, SoulThread::get_remaining_wait)
+            }
+            SoulWeave::Parallel(threads) => threads
+                .iter()
+                .map(SoulThread::get_remaining_wait)
+                .fold(0.0, f32::max),
+            SoulWeave::Race(threads) => threads
+                .iter()
+                .map(SoulThread::get_remaining_wait)
+                .reduce(f32::min)
+                .unwrap_or(0.0),
+            SoulWeave::Repeat { current, .. } => current
+                .as_ref()
+                .map_or(0.0, |thread| thread.get_remaining_wait()),
+        }
+    }
+
+    // soul threads not over yet, taken out when the weave is dropped
+    fn take_threads(&mut self) -> Vec<SoulThread> {
+        match self {
+            SoulWeave::Sequence(threads) => threads.drain(..).collect(),
+            SoulWeave::Parallel(threads) | SoulWeave::Race(threads) => std::mem::take(threads),
+            SoulWeave::Repeat { current, .. } => {
+                current.take().into_iter().map(|thread| *thread).collect()
+            }
+        }
+    }
+
+    #[cfg(feature = "hot_reload")]
+    fn is_owned_by(&self, image: &LibraryImage) -> bool {
+        match self {
+            SoulWeave::Sequence(threads) => threads.iter().any(|thread| thread.is_owned_by(image)),
+            SoulWeave::Parallel(threads) | SoulWeave::Race(threads) => {
+                threads.iter().any(|thread| thread.is_owned_by(image))
+            }
+            SoulWeave::Repeat {
+                factory, current, ..
+            } => {
+                image.owns(&**factory)
+                    || current
+                        .as_ref()
+                        .is_some_and(|thread| thread.is_owned_by(image))
+            }
+        }
+    }
+}
+
 pub struct SoulThread {
     name: String,
     state: EssenceAspect,
-    generator: Box<dyn FnMut() -> EssenceAspect + 'static>,
-    is_waiting: bool,
-    amount_to_wait: f32,
+    essence: SoulEssence,
+    waiting: Option<ActiveWait>,
+    // generator steps allowed per update while it keeps returning `Running`
+    step_budget: u32,
+    control: Rc<RefCell<SoulThreadControl>>,
+}
+
+// shared by a soul thread and its handles
+struct SoulThreadControl {
+    id: u64,
+    name: String,
+    state: EssenceAspect,
+    remaining_wait: f32,
+    paused: bool,
+    stopped: bool,
+    finished: bool,
+    // forged object the soul thread dies with, and waits on while it is inactive
+    owner: Option<uuid::Uuid>,
+    owner_active: Option<Rc<Cell<bool>>>,
+    on_finished: Vec<Box<dyn FnOnce()>>,
+}
+
+impl SoulThreadControl {
+    fn is_paused(&self) -> bool {
+        self.paused
+            || self
+                .owner_active
+                .as_ref()
+                .is_some_and(|active| !active.get())
+    }
+
+    // handles see a stopped soul thread as finished right away
+    fn stop(&mut self) {
+        if self.stopped || self.finished {
+            return;
+        }
+        self.stopped = true;
+        self.state = EssenceAspect::Finished;
+        self.remaining_wait = 0.0;
+        foretell(OmenLevel::Debug, OmenCategory::SoulThread, || {
+            format!("Soul thread {} stopped", self.name)
+        });
+    }
+}
+
+/// Controls one soul thread, returned by `LostRealm::add_soul_thread`.
+/// Names may repeat, handles always point at the same soul thread.
+#[derive(Clone)]
+pub struct SoulThreadHandle {
+    control: Rc<RefCell<SoulThreadControl>>,
+}
+
+impl SoulThreadHandle {
+    pub fn get_id(&self) -> u64 {
+        self.control.borrow().id
+    }
+
+    pub fn get_name(&self) -> String {
+        self.control.borrow().name.clone()
+    }
+
+    /// State after the last time the soul thread was resumed
+    pub fn get_state(&self) -> EssenceAspect {
+        self.control.borrow().state.clone()
+    }
+
+    /// Seconds left when waiting on time, 0.0 otherwise
+    pub fn get_remaining_wait(&self) -> f32 {
+        self.control.borrow().remaining_wait
+    }
+
+    /// Forged object owning the soul thread, see `LostRealm::start_soul_thread_on`
+    pub fn get_owner(&self) -> Option<uuid::Uuid> {
+        self.control.borrow().owner
+    }
+
+    /// The soul thread is skipped until `resume`, its waits do not run meanwhile
+    pub fn pause(&self) {
+        self.control.borrow_mut().paused = true;
+    }
+
+    pub fn resume(&self) {
+        self.control.borrow_mut().paused = false;
+    }
+
+    /// Paused with `pause`, or while the forged object owning it is inactive
+    pub fn is_paused(&self) -> bool {
+        self.control.borrow().is_paused()
+    }
+
+    /// The soul thread is not resumed anymore and is dropped on the next update,
+    /// its state is `Finished` from now on
+    pub fn stop(&self) {
+        self.control.borrow_mut().stop();
+    }
+
+    /// Finished or stopped
+    pub fn is_finished(&self) -> bool {
+        let control = self.control.borrow();
+        control.finished || control.stopped
+    }
+
+    /// Called once the soul thread finished or was stopped and is dropped,
+    /// right away when it already was
+    pub fn on_finished(&self, callback: impl FnOnce() + 'static) {
+        if self.control.borrow().finished {
+            callback();
+            return;
+        }
+        self.control
+            .borrow_mut()
+            .on_finished
+            .push(Box::new(callback));
+    }
+}
+
+impl PartialEq for SoulThreadHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.control, &other.control)
+    }
+}
+
+impl std::fmt::Debug for SoulThreadHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let control = self.control.borrow();
+        f.debug_struct("SoulThreadHandle")
+            .field("id", &control.id)
+            .field("name", &control.name)
+            .field("state", &control.state)
+            .field("paused", &control.paused)
+            .field("remaining_wait", &control.remaining_wait)
+            .finish()
+    }
+}
+
+impl std::fmt::Display for SoulThreadHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let control = self.control.borrow();
+        write!(f, "#{} {}", control.id, control.name)
+    }
 }
 
 impl SoulThread {
     // Constructor to create a new coroutine
     pub fn new(name: &str, generator: impl FnMut() -> EssenceAspect + 'static) -> Self {
+        Self::with_essence(name, SoulEssence::Generator(Box::new(generator)))
+    }
+
+    /// Like `new` but the generator gets the realm, to move objects, forge them or add events.
+    /// It runs after the objects are updated, and can add or stop soul threads.
+    pub fn with_realm(
+        name: &str,
+        generator: impl FnMut(&mut LostRealm) -> EssenceAspect + 'static,
+    ) -> Self {
+        Self::with_essence(name, SoulEssence::RealmGenerator(Box::new(generator)))
+    }
+
+    /// Soul thread running an `async` block, polled once per update until it completes.
+    /// It can await `wait_seconds`, `wait_frames`, `wait_event`, `wait_take_event` and `wait_for`.
+    pub fn from_future(name: &str, future: impl Future<Output = ()> + 'static) -> Self {
+        Self::with_essence(name, SoulEssence::Future(Box::pin(future)))
+    }
+
+    /// Runs the soul threads one after the other, finishing after the last one
+    pub fn sequence(name: &str, threads: impl IntoIterator<Item = SoulThread>) -> Self {
+        let threads = threads.into_iter().collect();
+        Self::with_essence(name, SoulEssence::Weave(SoulWeave::Sequence(threads)))
+    }
+
+    /// Runs the soul threads together, finishing once all of them are done
+    pub fn parallel(name: &str, threads: impl IntoIterator<Item = SoulThread>) -> Self {
+        let threads = threads.into_iter().collect();
+        Self::with_essence(name, SoulEssence::Weave(SoulWeave::Parallel(threads)))
+    }
+
+    /// Runs the soul threads together, finishing once one of them is done and stopping the others
+    pub fn race(name: &str, threads: impl IntoIterator<Item = SoulThread>) -> Self {
+        let threads = threads.into_iter().collect();
+        Self::with_essence(name, SoulEssence::Weave(SoulWeave::Race(threads)))
+    }
+
+    /// Runs a soul thread made by `factory` `times` times, one after the other
+    pub fn repeat(name: &str, times: u32, factory: impl FnMut() -> SoulThread + 'static) -> Self {
+        Self::with_essence(
+            name,
+            SoulEssence::Weave(SoulWeave::Repeat {
+                remaining: Some(times),
+                factory: Box::new(factory),
+                current: None,
+            }),
+        )
+    }
+
+    /// Runs a soul thread made by `factory` again each time it finishes, until stopped
+    pub fn repeat_forever(name: &str, factory: impl FnMut() -> SoulThread + 'static) -> Self {
+        Self::with_essence(
+            name,
+            SoulEssence::Weave(SoulWeave::Repeat {
+                remaining: None,
+                factory: Box::new(factory),
+                current: None,
+            }),
+        )
+    }
+
+    fn with_essence(name: &str, essence: SoulEssence) -> Self {
         Self {
             name: name.to_owned(),
             state: EssenceAspect::Running,
-            generator: Box::new(generator),
-            is_waiting: false,
-            amount_to_wait: 0.0,
+            essence,
+            waiting: None,
+            step_budget: 1,
+            control: Rc::new(RefCell::new(SoulThreadControl {
+                id: 0,
+                name: name.to_owned(),
+                state: EssenceAspect::Running,
+                remaining_wait: 0.0,
+                paused: false,
+                stopped: false,
+                finished: false,
+                owner: None,
+                owner_active: None,
+                on_finished: Vec::new(),
+            })),
         }
     }
 
-    // Function to resume execution of the coroutine
-    fn resume(&mut self) -> Option<TemporalPause> {
-        match self.state {
-            EssenceAspect::Running => {
-                let next_state = (self.generator)();
-                self.state = next_state;
-                self.resume()
-            }
-            EssenceAspect::Yielded(value) => {
-                self.state = EssenceAspect::Running;
-                Some(value)
-            }
-            EssenceAspect::Finished => {
-                self.state = EssenceAspect::Finished;
-                None
+    pub fn get_handle(&self) -> SoulThreadHandle {
+        SoulThreadHandle {
+            control: self.control.clone(),
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.control.borrow().is_paused()
+    }
+
+    /// Binds the soul thread to a forged object, it stops when the object is destroyed
+    /// and waits while the object is inactive
+    pub fn set_owner(&mut self, owner: &ForgedObject) {
+        let mut control = self.control.borrow_mut();
+        control.owner = Some(owner.id);
+        control.owner_active = Some(owner.active.clone());
+    }
+
+    pub fn get_owner(&self) -> Option<uuid::Uuid> {
+        self.control.borrow().owner
+    }
+
+    /// How many times the generator may run in one update while it returns `Running`, at least 1.
+    /// Once spent, the soul thread goes on with the next update
+    pub fn set_step_budget(&mut self, steps: u32) {
+        self.step_budget = steps.max(1);
+    }
+
+    pub fn get_step_budget(&self) -> u32 {
+        self.step_budget
+    }
+
+    // whether the manager should resume it on this update
+    fn is_resumable(&mut self) -> bool {
+        if self.control.borrow().stopped {
+            self.state = EssenceAspect::Finished;
+        }
+        self.state != EssenceAspect::Finished && !self.is_paused()
+    }
+
+    // handles see the state of the last resume
+    fn sync_control(&self) {
+        let remaining_wait = self.get_remaining_wait();
+        let mut control = self.control.borrow_mut();
+        control.state = self.state.clone();
+        control.remaining_wait = remaining_wait;
+    }
+
+    // Function to resume execution of the coroutine, steps the generator until it waits
+    // or its step budget is spent
+    fn resume(&mut self) -> Option<SoulWait> {
+        if self.state == EssenceAspect::Finished {
+            return None;
+        }
+        for _ in 0..self.step_budget {
+            let next_state = match &mut self.essence {
+                SoulEssence::Generator(generator) => generator(),
+                SoulEssence::RealmGenerator(generator) => {
+                    let Some(lost_realm) = with_soul_context(|context| context.borrow().lost_realm)
+                    else {
+                        // tried again on the next update
+                        return Some(SoulWait::Frames(1));
+                    };
+                    // the realm outlives the update that put it in the context
+                    generator(unsafe { &mut *lost_realm })
+                }
+                SoulEssence::Future(_) | SoulEssence::Weave(_) => return None,
+            };
+            self.state = next_state;
+            match &self.state {
+                EssenceAspect::Running => continue,
+                EssenceAspect::Yielded(value) => {
+                    return Some(SoulWait::Seconds(value.amount_in_seconds))
+                }
+                EssenceAspect::Awaiting(wait) => return Some(wait.clone()),
+                EssenceAspect::Finished => return None,
             }
         }
+        Some(SoulWait::Frames(1))
     }
 
+    /// Resumes the soul thread outside of a realm, waits on the realm, events and other threads never end
     pub fn update(&mut self, delta_time: f32) {
-        if self.is_waiting {
-            self.amount_to_wait -= delta_time;
+        let previous = with_soul_context(|context| {
+            context.replace(SoulContext {
+                delta_time,
+                unscaled_delta_time: delta_time,
+                ..Default::default()
+            })
+        });
+        if self.is_resumable() {
+            self.tick();
+        }
+        with_soul_context(|context| context.replace(previous));
+    }
 
-            if self.amount_to_wait > 0.0 {
-                return;
+    // resumes the soul thread with the update in `SOUL_CONTEXT`
+    fn tick(&mut self) {
+        self.resume_essence();
+        self.sync_control();
+    }
+
+    // resumes a soul thread inside a weave, true once it is over
+    fn tick_inner(&mut self) -> bool {
+        if self.is_resumable() {
+            self.tick();
+        }
+        self.state == EssenceAspect::Finished
+    }
+
+    fn resume_essence(&mut self) {
+        if let SoulEssence::Weave(weave) = &mut self.essence {
+            if weave.tick() {
+                self.state = EssenceAspect::Finished;
             }
-            self.is_waiting = false;
+            return;
+        }
+        if let SoulEssence::Future(future) = &mut self.essence {
+            with_soul_context(|context| context.borrow_mut().remaining_wait = None);
+            // awaited helpers are checked again on every update, so nothing needs waking
+            match future
+                .as_mut()
+                .poll(&mut Context::from_waker(Waker::noop()))
+            {
+                Poll::Ready(()) => self.state = EssenceAspect::Finished,
+                Poll::Pending => {
+                    let remaining_wait =
+                        with_soul_context(|context| context.borrow_mut().remaining_wait.take());
+                    self.waiting = remaining_wait.map(ActiveWait::Seconds);
+                }
+            }
+            return;
         }
 
-        if let Some(res) = self.resume() {
-            self.is_waiting = true;
-            self.amount_to_wait = res.amount_in_seconds;
+        if let Some(waiting) = self.waiting.as_mut() {
+            if !waiting.advance() {
+                return;
+            }
+            self.waiting = None;
         }
+
+        self.waiting = self.resume().map(ActiveWait::start);
     }
 
     pub fn stop(&mut self) {
         self.state = EssenceAspect::Finished;
+        self.control.borrow_mut().stop();
+    }
+
+    #[cfg(feature = "hot_reload")]
+    pub(crate) fn is_owned_by(&self, image: &LibraryImage) -> bool {
+        match &self.essence {
+            SoulEssence::Generator(generator) => image.owns(&**generator),
+            SoulEssence::RealmGenerator(generator) => image.owns(&**generator),
+            SoulEssence::Future(future) => image.owns(&**future),
+            SoulEssence::Weave(weave) => weave.is_owned_by(image),
+        }
+    }
+
+    #[cfg(all(test, feature = "hot_reload"))]
+    pub(crate) fn generator_ptr(&self) -> Option<*const (dyn FnMut() -> EssenceAspect + 'static)> {
+        match &self.essence {
+            SoulEssence::Generator(generator) => Some(&**generator),
+            _ => None,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_state(&self) -> EssenceAspect {
+        self.state.clone()
+    }
+
+    /// Seconds left when waiting on time, 0.0 otherwise
+    pub fn get_remaining_wait(&self) -> f32 {
+        if let SoulEssence::Weave(weave) = &self.essence {
+            return weave.get_remaining_wait();
+        }
+        self.waiting
+            .as_ref()
+            .map_or(0.0, ActiveWait::get_remaining_seconds)
     }
 }
 
 pub struct SoulThreadManager {
     soul_threads: Vec<SoulThread>,
+    // every soul thread not finished yet, even while they are taken out to resume
+    handles: Vec<SoulThreadHandle>,
+    next_id: u64,
+    destiny_rift_manager: Option<Rc<RefCell<DestinyRiftManager>>>,
+    #[cfg(feature = "profiling")]
+    eon_ledger: Option<Rc<RefCell<EonLedger>>>,
 }
 
 impl SoulThreadManager {
     pub fn new() -> Self {
         Self {
             soul_threads: Vec::new(),
+            handles: Vec::new(),
+            next_id: 0,
+            destiny_rift_manager: None,
+            #[cfg(feature = "profiling")]
+            eon_ledger: None,
         }
     }
 
-    pub fn add_thread(&mut self, thread: SoulThread) {
+    /// Events `wait_event` and `wait_take_event` look at
+    pub fn set_destiny_rift_manager(
+        &mut self,
+        destiny_rift_manager: Rc<RefCell<DestinyRiftManager>>,
+    ) {
+        self.destiny_rift_manager = Some(destiny_rift_manager);
+    }
+
+    #[cfg(feature = "profiling")]
+    pub fn set_eon_ledger(&mut self, eon_ledger: Rc<RefCell<EonLedger>>) {
+        self.eon_ledger = Some(eon_ledger);
+    }
+
+    pub fn add_thread(&mut self, thread: SoulThread) -> SoulThreadHandle {
+        thread.control.borrow_mut().id = self.next_id;
+        self.next_id += 1;
+        let handle = thread.get_handle();
+        foretell(OmenLevel::Debug, OmenCategory::SoulThread, || {
+            format!("Soul thread {} started", handle)
+        });
+        self.handles.push(handle.clone());
         self.soul_threads.push(thread);
+        handle
     }
 
+    /// Resumes the soul threads without a realm, soul threads made with `SoulThread::with_realm` wait,
+    /// `SoulWait::Until` never ends and real time follows `delta_time`
     pub fn update(&mut self, delta_time: f32) {
-        for thread in self.soul_threads.iter_mut() {
-            if thread.state == EssenceAspect::Finished {
-                continue;
+        let (mut soul_threads, previous) = self.begin_resume(None, delta_time, delta_time);
+        for thread in soul_threads.iter_mut() {
+            if thread.is_resumable() {
+                #[cfg(feature = "profiling")]
+                let started = std::time::Instant::now();
+                thread.tick();
+                #[cfg(feature = "profiling")]
+                Self::record_resume(&self.eon_ledger, thread, started);
             }
-            thread.update(delta_time);
         }
+        let finished = self.end_resume(soul_threads, previous);
+        Self::finish(finished);
+    }
 
-        self.soul_threads
-            .retain(|thread| thread.state != EssenceAspect::Finished);
+    // the manager is not borrowed while soul threads resume, they get the realm mutably
+    pub(crate) fn update_in_realm(
+        soul_threads_manager: &RefCell<SoulThreadManager>,
+        lost_realm: &mut LostRealm,
+        delta_time: f32,
+        unscaled_delta_time: f32,
+    ) {
+        let (mut soul_threads, previous) = soul_threads_manager.borrow_mut().begin_resume(
+            Some(lost_realm as *mut LostRealm),
+            delta_time,
+            unscaled_delta_time,
+        );
+        #[cfg(feature = "profiling")]
+        let eon_ledger = soul_threads_manager.borrow().eon_ledger.clone();
+        for thread in soul_threads.iter_mut() {
+            // checked right before resuming, an earlier soul thread may have destroyed the owner
+            if let Some(owner) = thread.get_owner() {
+                if lost_realm.get_forged_object_by_id(owner).is_none() {
+                    thread.stop();
+                }
+            }
+            if thread.is_resumable() {
+                #[cfg(feature = "profiling")]
+                let started = std::time::Instant::now();
+                thread.tick();
+                #[cfg(feature = "profiling")]
+                Self::record_resume(&eon_ledger, thread, started);
+            }
+        }
+        let finished = soul_threads_manager
+            .borrow_mut()
+            .end_resume(soul_threads, previous);
+        Self::finish(finished);
     }
 
-    pub fn stop_all(&mut self) {
-        self.soul_threads
-            .iter_mut()
-            .for_each(|thread| thread.stop());
+    // takes the soul threads out so they can add soul threads while resuming
+    fn begin_resume(
+        &mut self,
+        lost_realm: Option<*mut LostRealm>,
+        delta_time: f32,
+        unscaled_delta_time: f32,
+    ) -> (Vec<SoulThread>, SoulContext) {
+        let previous = with_soul_context(|context| {
+            context.replace(SoulContext {
+                delta_time,
+                unscaled_delta_time,
+                lost_realm,
+                destiny_rift_manager: self.destiny_rift_manager.clone(),
+                remaining_wait: None,
+            })
+        });
+        (std::mem::take(&mut self.soul_threads), previous)
     }
 
-    pub fn stop_by_name(&mut self, name: &str) {
-        let soul_thread = self
-            .soul_threads
-            .iter_mut()
-            .find(|thread| thread.name == name);
-        if let Some(soul_thread) = soul_thread {
-            soul_thread.stop();
+    #[cfg(feature = "profiling")]
+    fn record_resume(
+        eon_ledger: &Option<Rc<RefCell<EonLedger>>>,
+        thread: &SoulThread,
+        started: std::time::Instant,
+    ) {
+        if let Some(eon_ledger) = eon_ledger {
+            eon_ledger
+                .borrow_mut()
+                .record(LedgerCategory::SoulThreadResume, &thread.name, started);
         }
     }
-}
 
+    // puts the soul threads back and drops the finished ones, returning them
+    fn end_resume(
+        &mut self,
+        mut soul_threads: Vec<SoulThread>,
+        previous: SoulContext,
+    ) -> Vec<SoulThread> {
+        with_soul_context(|context| context.replace(previous));
+        // soul threads added while resuming run from the next update
+        soul_threads.append(&mut self.soul_threads);
+        let (finished, running) = soul_threads.into_iter().partition::<Vec<_>, _>(|thread| {
+            thread.control.borrow().stopped || thread.state == EssenceAspect::Finished
+        });
+        self.soul_threads = running;
+        self.handles.retain(|handle| {
+            !finished
+                .iter()
+                .any(|thread| Rc::ptr_eq(&thread.control, &handle.control))
+        });
+        finished
+    }
+
+    // called without borrowing the manager, completion callbacks may add soul threads
+    fn finish(finished: Vec<SoulThread>) {
+        for mut thread in finished {
+            // a weave stopped early takes down the soul threads it still runs
+            if let SoulEssence::Weave(weave) = &mut thread.essence {
+                let mut inner = weave.take_threads();
+                inner.iter_mut().for_each(SoulThread::stop);
+                Self::finish(inner);
+            }
+            thread.state = EssenceAspect::Finished;
+            thread.sync_control();
+            let on_finished = {
+                let mut control = thread.control.borrow_mut();
+                control.finished = true;
+                std::mem::take(&mut control.on_finished)
+            };
+            foretell(OmenLevel::Debug, OmenCategory::SoulThread, || {
+                format!("Soul thread {} finished", thread.get_handle())
+            });
+            for callback in on_finished {
+                callback();
+            }
+        }
+    }
+
+    pub fn get_soul_threads(&self) -> &[SoulThread] {
+        &self.soul_threads
+    }
+
+    /// Every soul thread not dropped yet, including the ones resuming right now
+    pub fn get_soul_thread_handles(&self) -> &[SoulThreadHandle] {
+        &self.handles
+    }
+
+    #[cfg(feature = "hot_reload")]
+    pub(crate) fn retain_soul_threads(&mut self, mut keep: impl FnMut(&SoulThread) -> bool) {
+        let (kept, dropped) = std::mem::take(&mut self.soul_threads)
+            .into_iter()
+            .partition::<Vec<_>, _>(|thread| keep(thread));
+        self.soul_threads = kept;
+        self.handles.retain(|handle| {
+            !dropped
+                .iter()
+                .any(|thread| Rc::ptr_eq(&thread.control, &handle.control))
+        });
+    }
+
+    // completion callbacks outlive their soul thread through the handles
+    #[cfg(feature = "hot_reload")]
+    pub(crate) fn retain_finished_callbacks(
+        &mut self,
+        mut keep: impl FnMut(&dyn FnOnce()) -> bool,
+    ) {
+        for handle in &self.handles {
+            handle
+                .control
+                .borrow_mut()
+                .on_finished
+                .retain(|callback| keep(&**callback));
+        }
+    }
+
+    /// Soul threads owned by a forged object
+    pub fn get_soul_thread_handles_on(&self, owner: uuid::Uuid) -> Vec<SoulThreadHandle> {
+        self.handles
+            .iter()
+            .filter(|handle| handle.get_owner() == Some(owner))
+            .cloned()
+            .collect()
+    }
+
+    pub(crate) fn stop_owned_by(&self, owner: uuid::Uuid) {
+        self.handles
+            .iter()
+            .filter(|handle| handle.get_owner() == Some(owner))
+            .for_each(SoulThreadHandle::stop);
+    }
+
+    pub fn stop_all(&mut self) {
+        self.handles.iter().for_each(SoulThreadHandle::stop);
+    }
+
+    /// Stops the first soul thread with that name,
+    /// see `SoulThreadHandle::stop` to stop one in particular
+    pub fn stop_by_name(&mut self, name: &str) -> Result<(), LostLostLandsFaultSoulThread> {
+        let handle = self
+            .handles
+            .iter()
+            .find(|handle| !handle.is_finished() && handle.get_name() == name)
+            .ok_or_else(|| LostLostLandsFaultSoulThread::SoulThreadNotFound(name.to_string()))?;
+        handle.stop();
+        Ok(())
+    }
+}
 
 impl Default for SoulThreadManager {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+// what awaited helpers know about the update polling them
+#[derive(Default)]
+struct SoulContext {
+    delta_time: f32,
+    unscaled_delta_time: f32,
+    lost_realm: Option<*mut LostRealm>,
+    destiny_rift_manager: Option<Rc<RefCell<DestinyRiftManager>>>,
+    remaining_wait: Option<f32>,
+}
+
+thread_local! {
+    static SOUL_CONTEXT: RefCell<SoulContext> = RefCell::new(SoulContext::default());
+}
+
+// the soul context of this thread, the host's one inside a plugin
+fn with_soul_context<R>(f: impl FnOnce(&RefCell<SoulContext>) -> R) -> R {
+    match host_bindings() {
+        // the host is built like the plugin, so is its soul context
+        Some(host) => f(unsafe { &*((host.soul_context)() as *const RefCell<SoulContext>) }),
+        None => SOUL_CONTEXT.with(f),
+    }
+}
+
+#[cfg(feature = "hot_reload")]
+pub(crate) extern "C" fn local_soul_context() -> *const std::ffi::c_void {
+    SOUL_CONTEXT.with(|context| context as *const RefCell<SoulContext> as *const std::ffi::c_void)
+}
+
+/// Completes once `seconds` of scaled time went by, counted from the next update
+pub fn wait_seconds(seconds: f32) -> WaitSeconds {
+    WaitSeconds {
+        seconds,
+        remaining: None,
+    }
+}
+
+/// Completes on the `frames`th next update, right away for 0
+pub fn wait_frames(frames: u32) -> WaitFrames {
+    WaitFrames {
+        frames,
+        remaining: None,
+    }
+}
+
+/// Completes once an event of type `T` is added after the wait started, the event stays queued
+pub fn wait_event<T: 'static + DestinyRift>() -> WaitEvent<T> {
+    WaitEvent { reader: None }
+}
+
+/// Completes with the oldest event of type `T` as soon as one is queued, taking it out of the queue
+pub fn wait_take_event<T: 'static + DestinyRift>() -> WaitTakeEvent<T> {
+    WaitTakeEvent {
+        marker: PhantomData,
+    }
+}
+
+/// Runs `f` with the realm resuming the current soul thread, `None` outside of `LostRealm::update`
+pub fn with_lost_realm<R>(f: impl FnOnce(&mut LostRealm) -> R) -> Option<R> {
+    let lost_realm = with_soul_context(|context| context.borrow().lost_realm)?;
+    // the realm outlives the update that put it in the context
+    Some(f(unsafe { &mut *lost_realm }))
+}
+
+/// Completes once `seconds` of real time went by, whatever the time scale
+pub fn wait_real_seconds(seconds: f32) -> WaitFor {
+    wait_for(SoulWait::RealSeconds(seconds))
+}
+
+/// Completes on the first update where the predicate holds, checked from the next update
+pub fn wait_until(predicate: impl Fn(&LostRealm) -> bool + 'static) -> WaitFor {
+    wait_for(SoulWait::until(predicate))
+}
+
+/// Completes once the soul thread of the handle finished or was stopped
+pub fn wait_soul_thread(handle: &SoulThreadHandle) -> WaitFor {
+    wait_for(SoulWait::soul_thread(handle))
+}
+
+/// Completes when the wait would resume a soul thread that yielded `EssenceAspect::Awaiting(wait)`
+pub fn wait_for(wait: SoulWait) -> WaitFor {
+    WaitFor {
+        wait: Some(wait),
+        active: None,
+    }
+}
+
+pub struct WaitFor {
+    wait: Option<SoulWait>,
+    active: Option<ActiveWait>,
+}
+
+impl Future for WaitFor {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _context: &mut Context) -> Poll<()> {
+        if let Some(wait) = self.wait.take() {
+            self.active = Some(ActiveWait::start(wait));
+            return Poll::Pending;
+        }
+        let Some(active) = self.active.as_mut() else {
+            return Poll::Ready(());
+        };
+        if active.advance() {
+            self.active = None;
+            return Poll::Ready(());
+        }
+        if let ActiveWait::Seconds(seconds) | ActiveWait::RealSeconds(seconds) = active {
+            let remaining = *seconds;
+            with_soul_context(|context| context.borrow_mut().remaining_wait = Some(remaining));
+        }
+        Poll::Pending
+    }
+}
+
+pub struct WaitSeconds {
+    seconds: f32,
+    remaining: Option<f32>,
+}
+
+impl Future for WaitSeconds {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _context: &mut Context) -> Poll<()> {
+        let remaining = match self.remaining {
+            None => self.seconds,
+            Some(remaining) => remaining - with_soul_context(|context| context.borrow().delta_time),
+        };
+        if self.remaining.is_some() && remaining <= 0.0 {
+            return Poll::Ready(());
+        }
+        self.remaining = Some(remaining);
+        with_soul_context(|context| context.borrow_mut().remaining_wait = Some(remaining));
+        Poll::Pending
+    }
+}
+
+pub struct WaitFrames {
+    frames: u32,
+    remaining: Option<u32>,
+}
+
+impl Future for WaitFrames {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _context: &mut Context) -> Poll<()> {
+        let remaining = match self.remaining {
+            None => self.frames,
+            Some(remaining) => remaining.saturating_sub(1),
+        };
+        if remaining == 0 {
+            return Poll::Ready(());
+        }
+        self.remaining = Some(remaining);
+        Poll::Pending
+    }
+}
+
+pub struct WaitEvent<T> {
+    reader: Option<EventReader<T>>,
+}
+
+impl<T: 'static + DestinyRift> Future for WaitEvent<T> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _context: &mut Context) -> Poll<()> {
+        let Some(destiny_rift_manager) =
+            with_soul_context(|context| context.borrow().destiny_rift_manager.clone())
+        else {
+            return Poll::Pending;
+        };
+        let destiny_rift_manager = destiny_rift_manager.borrow();
+        match self.reader.as_mut() {
+            None => {
+                self.reader = Some(destiny_rift_manager.reader_at_end::<T>());
+                Poll::Pending
+            }
+            Some(reader) if destiny_rift_manager.count_unread_events(reader) == 0 => Poll::Pending,
+            Some(_) => Poll::Ready(()),
+        }
+    }
+}
+
+pub struct WaitTakeEvent<T> {
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static + DestinyRift> Future for WaitTakeEvent<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, _context: &mut Context) -> Poll<T> {
+        let Some(destiny_rift_manager) =
+            with_soul_context(|context| context.borrow().destiny_rift_manager.clone())
+        else {
+            return Poll::Pending;
+        };
+        let event = destiny_rift_manager.borrow_mut().take_event::<T>();
+        match event {
+            Some(event) => Poll::Ready(event),
+            None => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::{
+        forged_trait::ForgedTrait,
+        testing::{test_rift, test_trait},
+    };
+
+    struct Chime(u32);
+    test_rift!(Chime);
+
+    type SoulLog = Rc<RefCell<Vec<String>>>;
+
+    fn manager_with_rifts() -> (SoulThreadManager, Rc<RefCell<DestinyRiftManager>>) {
+        let destiny_rift_manager = Rc::new(RefCell::new(DestinyRiftManager::new()));
+        let mut manager = SoulThreadManager::new();
+        manager.set_destiny_rift_manager(destiny_rift_manager.clone());
+        (manager, destiny_rift_manager)
+    }
+
+    #[test]
+    fn async_soul_thread_waits_seconds_then_frames() {
+        let log = SoulLog::default();
+        let mut manager = SoulThreadManager::new();
+        manager.add_thread(SoulThread::from_future("vigil", {
+            let log = log.clone();
+            async move {
+                log.borrow_mut().push("start".to_string());
+                wait_seconds(1.0).await;
+                log.borrow_mut().push("seconds".to_string());
+                wait_frames(2).await;
+                log.borrow_mut().push("frames".to_string());
+            }
+        }));
+        manager.update(0.5);
+        manager.update(0.5);
+        assert_eq!(*log.borrow(), ["start"]);
+        manager.update(0.5);
+        assert_eq!(*log.borrow(), ["start", "seconds"]);
+        manager.update(0.5);
+        assert_eq!(manager.get_soul_threads().len(), 1);
+        manager.update(0.5);
+        assert_eq!(*log.borrow(), ["start", "seconds", "frames"]);
+        assert!(manager.get_soul_threads().is_empty());
+    }
+
+    #[test]
+    fn wait_event_leaves_the_event_queued() {
+        let log = SoulLog::default();
+        let (mut manager, destiny_rift_manager) = manager_with_rifts();
+        destiny_rift_manager
+            .borrow_mut()
+            .add_event(Box::new(Chime(1)));
+        manager.add_thread(SoulThread::from_future("listener", {
+            let log = log.clone();
+            async move {
+                wait_event::<Chime>().await;
+                log.borrow_mut().push("heard".to_string());
+            }
+        }));
+        // events queued before the wait started don't count
+        manager.update(0.0);
+        manager.update(0.0);
+        assert!(log.borrow().is_empty());
+        destiny_rift_manager
+            .borrow_mut()
+            .add_event(Box::new(Chime(2)));
+        manager.update(0.0);
+        assert_eq!(*log.borrow(), ["heard"]);
+        assert_eq!(destiny_rift_manager.borrow().count_events::<Chime>(), 2);
+    }
+
+    #[test]
+    fn wait_take_event_takes_the_oldest_event() {
+        let log = SoulLog::default();
+        let (mut manager, destiny_rift_manager) = manager_with_rifts();
+        manager.add_thread(SoulThread::from_future("taker", {
+            let log = log.clone();
+            async move {
+                let chime = wait_take_event::<Chime>().await;
+                log.borrow_mut().push(format!("took {}", chime.0));
+            }
+        }));
+        manager.update(0.0);
+        assert!(log.borrow().is_empty());
+        destiny_rift_manager
+            .borrow_mut()
+            .add_event(Box::new(Chime(1)));
+        destiny_rift_manager
+            .borrow_mut()
+            .add_event(Box::new(Chime(2)));
+        manager.update(0.0);
+        assert_eq!(*log.borrow(), ["took 1"]);
+        assert_eq!(destiny_rift_manager.borrow().count_events::<Chime>(), 1);
+    }
+
+    fn log_after(
+        log: &SoulLog,
+        entry: &str,
+        wait: impl Future<Output = ()> + 'static,
+    ) -> impl Future<Output = ()> {
+        let log = log.clone();
+        let entry = entry.to_string();
+        async move {
+            wait.await;
+            log.borrow_mut().push(entry);
+        }
+    }
+
+    #[test]
+    fn wait_real_seconds_ignores_the_time_scale() {
+        let log = SoulLog::default();
+        let mut lost_realm = LostRealm::new();
+        lost_realm.set_time_scale(0.0);
+        lost_realm.spawn_soul_thread("real", log_after(&log, "real", wait_real_seconds(1.0)));
+        lost_realm.spawn_soul_thread("scaled", log_after(&log, "scaled", wait_seconds(1.0)));
+        for frame in 1..=3 {
+            lost_realm.eonforge.borrow_mut().update(frame as f32 * 0.6);
+            lost_realm.update();
+        }
+        assert_eq!(*log.borrow(), ["real"]);
+    }
+
+    #[test]
+    fn wait_until_resumes_once_the_realm_matches() {
+        let log = SoulLog::default();
+        let mut lost_realm = LostRealm::new();
+        let wait = wait_until(|lost_realm| lost_realm.count_destiny_rift_events::<Chime>() > 0);
+        lost_realm.spawn_soul_thread("door", log_after(&log, "open", wait));
+        lost_realm.update();
+        lost_realm.update();
+        assert!(log.borrow().is_empty());
+        lost_realm.add_destiny_rift_event(Chime(1));
+        lost_realm.update();
+        assert_eq!(*log.borrow(), ["open"]);
+    }
+
+    #[test]
+    fn wait_soul_thread_resumes_once_the_other_thread_is_done() {
+        let log = SoulLog::default();
+        let mut manager = SoulThreadManager::new();
+        let mut yielded = false;
+        let first = manager.add_thread(SoulThread::new("first", move || {
+            if yielded {
+                return EssenceAspect::Finished;
+            }
+            yielded = true;
+            EssenceAspect::Awaiting(SoulWait::Frames(2))
+        }));
+        // a namesake does not hold the wait
+        manager.add_thread(SoulThread::new("first", || {
+            EssenceAspect::Awaiting(SoulWait::Frames(100))
+        }));
+        manager.add_thread(SoulThread::from_future(
+            "second",
+            log_after(&log, "second", wait_soul_thread(&first)),
+        ));
+        manager.update(0.0);
+        manager.update(0.0);
+        assert!(log.borrow().is_empty());
+        assert_eq!(manager.get_soul_threads().len(), 3);
+        // "first" finishes in this update, "second" sees it on the next one
+        manager.update(0.0);
+        manager.update(0.0);
+        assert_eq!(*log.borrow(), ["second"]);
+        assert_eq!(manager.get_soul_threads().len(), 1);
+    }
+
+    #[test]
+    fn realm_soul_threads_use_the_realm_and_add_soul_threads() {
+        let log = SoulLog::default();
+        let mut lost_realm = LostRealm::new();
+        lost_realm.add_soul_thread(SoulThread::with_realm("bell", {
+            let log = log.clone();
+            move |lost_realm| {
+                log.borrow_mut().push("bell".to_string());
+                let log = log.clone();
+                lost_realm.add_soul_thread(SoulThread::with_realm("echo", move |lost_realm| {
+                    log.borrow_mut().push("echo".to_string());
+                    lost_realm.add_destiny_rift_event(Chime(1));
+                    EssenceAspect::Finished
+                }));
+                lost_realm.stop_soul_thread_by_name("bell").unwrap();
+                EssenceAspect::Awaiting(SoulWait::Frames(1))
+            }
+        }));
+        // "echo" starts on the next update, "bell" stopped itself
+        lost_realm.update();
+        assert_eq!(*log.borrow(), ["bell"]);
+        lost_realm.update();
+        lost_realm.update();
+        assert_eq!(*log.borrow(), ["bell", "echo"]);
+        assert!(lost_realm.stop_soul_thread_by_name("bell").is_err());
+        assert!(lost_realm.stop_soul_thread_by_name("echo").is_err());
+    }
+
+    #[test]
+    fn realm_soul_threads_wait_outside_of_a_realm() {
+        let resumed = Rc::new(RefCell::new(false));
+        let mut manager = SoulThreadManager::new();
+        manager.add_thread(SoulThread::with_realm("bell", {
+            let resumed = resumed.clone();
+            move |_| {
+                *resumed.borrow_mut() = true;
+                EssenceAspect::Finished
+            }
+        }));
+        manager.update(0.0);
+        manager.update(0.0);
+        assert!(!*resumed.borrow());
+        assert_eq!(manager.get_soul_threads().len(), 1);
+        assert!(with_lost_realm(|_| ()).is_none());
+    }
+
+    #[test]
+    fn async_soul_threads_reach_the_realm() {
+        let mut lost_realm = LostRealm::new();
+        lost_realm.spawn_soul_thread("bell", async {
+            with_lost_realm(|lost_realm| lost_realm.add_destiny_rift_event(Chime(1))).unwrap();
+        });
+        lost_realm.update();
+        assert_eq!(lost_realm.count_destiny_rift_events::<Chime>(), 1);
+    }
+
+    // logs its name on each resume and finishes on the `resumes`th one
+    fn stepping(log: &SoulLog, name: &'static str, resumes: u32) -> SoulThread {
+        let log = log.clone();
+        let mut left = resumes;
+        SoulThread::new(name, move || {
+            log.borrow_mut().push(name.to_string());
+            left -= 1;
+            if left == 0 {
+                EssenceAspect::Finished
+            } else {
+                EssenceAspect::Awaiting(SoulWait::Frames(1))
+            }
+        })
+    }
+
+    fn run(manager: &mut SoulThreadManager, updates: u32) {
+        for _ in 0..updates {
+            manager.update(0.0);
+        }
+    }
+
+    #[test]
+    fn sequence_finishes_after_its_last_soul_thread() {
+        let log = SoulLog::default();
+        let mut manager = SoulThreadManager::new();
+        manager.add_thread(SoulThread::sequence(
+            "sequence",
+            [stepping(&log, "first", 2), stepping(&log, "second", 1)],
+        ));
+        run(&mut manager, 1);
+        assert_eq!(*log.borrow(), ["first"]);
+        run(&mut manager, 1);
+        assert_eq!(*log.borrow(), ["first", "first", "second"]);
+        assert!(manager.get_soul_threads().is_empty());
+    }
+
+    #[test]
+    fn parallel_finishes_once_every_soul_thread_is_done() {
+        let log = SoulLog::default();
+        let mut manager = SoulThreadManager::new();
+        manager.add_thread(SoulThread::parallel(
+            "parallel",
+            [stepping(&log, "quick", 1), stepping(&log, "slow", 3)],
+        ));
+        run(&mut manager, 2);
+        assert_eq!(manager.get_soul_threads().len(), 1);
+        run(&mut manager, 1);
+        assert_eq!(*log.borrow(), ["quick", "slow", "slow", "slow"]);
+        assert!(manager.get_soul_threads().is_empty());
+    }
+
+    #[test]
+    fn race_finishes_with_its_first_soul_thread() {
+        let log = SoulLog::default();
+        let mut manager = SoulThreadManager::new();
+        manager.add_thread(SoulThread::race(
+            "race",
+            [stepping(&log, "slow", 3), stepping(&log, "fast", 2)],
+        ));
+        run(&mut manager, 2);
+        assert!(manager.get_soul_threads().is_empty());
+        run(&mut manager, 1);
+        assert_eq!(*log.borrow(), ["slow", "fast", "slow", "fast"]);
+    }
+
+    #[test]
+    fn race_stops_only_the_soul_threads_it_leaves_behind() {
+        let log = SoulLog::default();
+        let (slow, fast) = (stepping(&log, "slow", 3), stepping(&log, "fast", 2));
+        let handles = (slow.get_handle(), fast.get_handle());
+        let mut manager = SoulThreadManager::new();
+        manager.add_thread(SoulThread::race("race", [slow, fast]));
+        run(&mut manager, 2);
+        let (slow, fast) = (handles.0.control.borrow(), handles.1.control.borrow());
+        assert!(fast.finished && !fast.stopped);
+        assert!(slow.finished && slow.stopped);
+    }
+
+    #[test]
+    fn repeat_runs_each_repetition_on_its_own_update() {
+        let log = SoulLog::default();
+        let mut manager = SoulThreadManager::new();
+        manager.add_thread(SoulThread::repeat("repeat", 3, {
+            let log = log.clone();
+            move || stepping(&log, "again", 1)
+        }));
+        run(&mut manager, 2);
+        assert_eq!(manager.get_soul_threads().len(), 1);
+        run(&mut manager, 1);
+        assert_eq!(*log.borrow(), ["again", "again", "again"]);
+        assert!(manager.get_soul_threads().is_empty());
+    }
+
+    // counts the steps of a generator returning `then` after `running` times `Running`
+    fn counting(running: u32, then: EssenceAspect) -> (SoulThread, Rc<Cell<u32>>) {
+        let steps = Rc::new(Cell::new(0));
+        let counter = steps.clone();
+        let thread = SoulThread::new("count", move || {
+            counter.set(counter.get() + 1);
+            if counter.get() > running {
+                return then.clone();
+            }
+            EssenceAspect::Running
+        });
+        (thread, steps)
+    }
+
+    #[test]
+    fn running_goes_on_with_the_next_update() {
+        let (mut thread, steps) = counting(u32::MAX, EssenceAspect::Finished);
+        thread.update(0.1);
+        assert_eq!(steps.get(), 1);
+        assert_eq!(thread.get_state(), EssenceAspect::Running);
+        thread.update(0.1);
+        thread.update(0.1);
+        assert_eq!(steps.get(), 3);
+    }
+
+    #[test]
+    fn step_budget_runs_several_steps_per_update() {
+        let (mut thread, steps) = counting(u32::MAX, EssenceAspect::Finished);
+        thread.set_step_budget(10);
+        thread.update(0.1);
+        assert_eq!(steps.get(), 10);
+        thread.update(0.1);
+        assert_eq!(steps.get(), 20);
+    }
+
+    #[test]
+    fn step_budget_is_at_least_one() {
+        let (mut thread, steps) = counting(u32::MAX, EssenceAspect::Finished);
+        thread.set_step_budget(0);
+        assert_eq!(thread.get_step_budget(), 1);
+        thread.update(0.1);
+        assert_eq!(steps.get(), 1);
+    }
+
+    #[test]
+    fn step_budget_ends_at_a_wait() {
+        let (mut thread, steps) = counting(2, EssenceAspect::Awaiting(SoulWait::Frames(2)));
+        thread.set_step_budget(10);
+        thread.update(0.1);
+        assert_eq!(steps.get(), 3);
+        assert_eq!(
+            thread.get_state(),
+            EssenceAspect::Awaiting(SoulWait::Frames(2))
+        );
+        thread.update(0.1);
+        assert_eq!(steps.get(), 3);
+    }
+
+    #[test]
+    fn step_budget_ends_when_finished() {
+        let (mut thread, steps) = counting(2, EssenceAspect::Finished);
+        thread.set_step_budget(10);
+        thread.update(0.1);
+        assert_eq!(steps.get(), 3);
+        assert_eq!(thread.get_state(), EssenceAspect::Finished);
+        thread.update(0.1);
+        assert_eq!(steps.get(), 3);
+    }
+
+    #[derive(Default)]
+    struct Lantern {
+        father: Option<String>,
+    }
+    test_trait!(Lantern);
+    impl ForgedTrait for Lantern {}
+
+    fn waiting(name: &str) -> SoulThread {
+        SoulThread::new(name, || EssenceAspect::Awaiting(SoulWait::Frames(100)))
+    }
+
+    #[test]
+    fn stopped_handle_reports_finished_right_away() {
+        let mut soul_threads_manager = SoulThreadManager::new();
+        let handle = soul_threads_manager.add_thread(waiting("flicker"));
+        soul_threads_manager.update(0.1);
+        assert!(matches!(handle.get_state(), EssenceAspect::Awaiting(_)));
+        handle.stop();
+        assert_eq!(handle.get_state(), EssenceAspect::Finished);
+        assert!(handle.is_finished());
+        assert_eq!(soul_threads_manager.get_soul_thread_handles().len(), 1);
+        soul_threads_manager.update(0.1);
+        assert!(soul_threads_manager.get_soul_thread_handles().is_empty());
+    }
+
+    #[test]
+    fn stopping_a_weave_finishes_its_inner_soul_threads() {
+        let finished = Rc::new(RefCell::new(Vec::new()));
+        let inner = |name: &'static str| {
+            let thread = waiting(name);
+            let finished = finished.clone();
+            thread
+                .get_handle()
+                .on_finished(move || finished.borrow_mut().push(name));
+            thread
+        };
+        let mut soul_threads_manager = SoulThreadManager::new();
+        let weave = SoulThread::sequence(
+            "weave",
+            [
+                inner("first"),
+                SoulThread::parallel("both", [inner("left"), inner("right")]),
+            ],
+        );
+        let handle = soul_threads_manager.add_thread(weave);
+        soul_threads_manager.update(0.1);
+        handle.stop();
+        soul_threads_manager.update(0.1);
+        assert_eq!(*finished.borrow(), vec!["first", "left", "right"]);
+    }
+
+    #[test]
+    fn inactive_owner_pauses_the_handle() {
+        let lost_realm = LostRealm::new();
+        let lamp = lost_realm
+            .forge_new_object("Lamp", (Lantern::default(),))
+            .unwrap();
+        let handle = lost_realm.start_soul_thread_on(lamp, waiting("glow"));
+        assert!(!handle.is_paused());
+        lamp.set_active(false);
+        assert!(handle.is_paused());
+        assert!(lost_realm.dump().soul_threads[0].paused);
+        lamp.set_active(true);
+        assert!(!handle.is_paused());
+    }
+}
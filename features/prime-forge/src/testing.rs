@@ -0,0 +1,47 @@
+// helpers shared by the unit tests
+
+/// Implements `EtherealFlow` and `DestinyRift` for test events
+macro_rules! test_rift {
+    ($($ty: ty),*) => {
+        $(impl $crate::EtherealFlow for $ty {
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+
+            fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+                self
+            }
+        }
+
+        impl $crate::destiny_rift::DestinyRift for $ty {})*
+    };
+}
+
+/// Implements `EtherealFlow` and `ForgedHierarchy` for test traits with a `father: Option<String>` field,
+/// `ForgedTrait` is left to the test
+macro_rules! test_trait {
+    ($($ty: ty),*) => {
+        $(impl $crate::EtherealFlow for $ty {
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+
+            fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+                self
+            }
+        }
+
+        impl $crate::forged_trait::ForgedHierarchy for $ty {
+            fn set_father(&mut self, father_id: String) {
+                self.father = Some(father_id);
+            }
+
+            fn get_father(&self) -> Option<String> {
+                self.father.clone()
+            }
+        })*
+    };
+}
+
+pub(crate) use test_rift;
+pub(crate) use test_trait;